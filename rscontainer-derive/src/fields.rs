@@ -0,0 +1,57 @@
+//! Construction of the fields, shared by the derive macros.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Expr, Field, Fields, Meta, Result};
+
+/// Returns the expression that constructs `Self` field by field, using the
+/// `resolver` variable for the dependencies. Without any dependencies the
+/// `Default` impl of `Self` is used instead.
+pub fn instance(fields: &Fields) -> Result<TokenStream> {
+    if !fields.is_empty() && !fields.iter().any(is_dependency) {
+        return Ok(quote! { <Self as ::std::default::Default>::default() });
+    }
+    let values = fields.iter().map(field_value).collect::<Result<Vec<_>>>()?;
+    Ok(match fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|f| &f.ident);
+            quote! { Self { #(#names: #values),* } }
+        }
+        Fields::Unnamed(_) => quote! { Self(#(#values),*) },
+        Fields::Unit => quote! { Self },
+    })
+}
+
+/// Returns whether the field is resolved from the container.
+fn is_dependency(field: &Field) -> bool {
+    field
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("shared") || attr.path().is_ident("owned"))
+}
+
+/// Returns the expression that constructs a field.
+fn field_value(field: &Field) -> Result<TokenStream> {
+    for attr in &field.attrs {
+        if attr.path().is_ident("shared") {
+            attr.meta.require_path_only()?;
+            return Ok(quote! { resolver.shared()? });
+        }
+        if attr.path().is_ident("owned") {
+            let ty = &field.ty;
+            let params = match &attr.meta {
+                Meta::Path(_) => quote! { ::std::default::Default::default() },
+                _ => {
+                    let params: Expr = attr.parse_args()?;
+                    quote! { #params }
+                }
+            };
+            return Ok(quote! { resolver.owned::<#ty>(#params)? });
+        }
+    }
+    // Spanned to the field, so a missing `Default` is reported on the field
+    // instead of on the derive.
+    let ty = &field.ty;
+    Ok(quote_spanned! {ty.span()=> <#ty as ::std::default::Default>::default() })
+}
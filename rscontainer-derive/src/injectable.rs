@@ -0,0 +1,34 @@
+//! Implementation of `#[injectable]`.
+
+use crate::shared;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Result};
+
+/// Generates the struct without the `#[inject]` attributes, and its
+/// `IShared` impl as if it was derived with the `#[shared]` attributes.
+pub fn expand(args: TokenStream, mut item: DeriveInput) -> Result<TokenStream> {
+    let mut input = item.clone();
+    if !args.is_empty() {
+        input.attrs.push(parse_quote!(#[shared(#args)]));
+    }
+    if let Data::Struct(data) = &mut input.data {
+        for attr in data.fields.iter_mut().flat_map(|f| &mut f.attrs) {
+            if attr.path().is_ident("inject") {
+                attr.meta.require_path_only()?;
+                *attr = parse_quote!(#[shared]);
+            }
+        }
+    }
+    let imp = shared::derive(input)?;
+
+    if let Data::Struct(data) = &mut item.data {
+        for field in data.fields.iter_mut() {
+            field.attrs.retain(|attr| !attr.path().is_ident("inject"));
+        }
+    }
+    Ok(quote! {
+        #item
+        #imp
+    })
+}
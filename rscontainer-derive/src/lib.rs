@@ -0,0 +1,78 @@
+//! Derive macros for rscontainer.
+//!
+//! Use these through the `derive` feature of rscontainer.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod fields;
+mod injectable;
+mod owned;
+mod shared;
+
+/// Derives `IShared` for a struct.
+///
+/// The pointer is `Arc<Mutex<Self>>` and the target is `Self`. Every field
+/// is constructed in declaration order:
+///
+/// * `#[shared]` resolves a `Shared<S>` field with `Resolver::shared()`.
+/// * `#[owned]` resolves an owned instance of the field type with
+///   `Resolver::owned()` and default parameters, `#[owned(expr)]` uses `expr`
+///   as the parameters.
+/// * Fields without an attribute use `Default::default()` of the field type.
+///
+/// If none of the fields has an attribute, the struct is constructed with
+/// its own `Default` impl instead.
+///
+/// The error type is `Infallible`, unless it is set with
+/// `#[shared(error = "Type")]` on the struct. The errors of the dependencies
+/// are converted with `From`, so a struct with dependencies that can fail
+/// needs an error type that they convert into.
+///
+/// The pointer can be changed with `#[shared(pointer = "Rc<RefCell<Self>>")]`,
+/// as long as every layer has a `new` function. With
+/// `#[shared(constructor = "path::to::function")]` the fields are ignored and
+/// the function, with the signature of `IShared::construct`, is called
+/// instead.
+#[proc_macro_derive(IShared, attributes(shared, owned))]
+pub fn derive_shared(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    shared::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `IOwned` for a struct.
+///
+/// The instance is `Self`, and it is constructed like with
+/// `#[derive(IShared)]`. The parameters are `()` and the error type is
+/// `Infallible`, unless they are set with
+/// `#[owned(params = "Type", error = "Type")]` on the struct. With
+/// `#[owned(constructor = "path::to::function")]` the fields are ignored and
+/// the function, with the signature of `IOwned::construct`, is called
+/// instead.
+///
+/// Can be combined with `#[derive(IShared)]` on the same struct.
+#[proc_macro_derive(IOwned, attributes(shared, owned))]
+pub fn derive_owned(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    owned::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `IShared` for a struct whose `#[inject]` fields are resolved
+/// with `Resolver::shared()`.
+///
+/// Works like `#[derive(IShared)]`, where `#[inject]` stands for `#[shared]`
+/// and the arguments, such as
+/// `#[injectable(pointer = "Rc<RefCell<Self>>", error = "MyError")]`, stand
+/// for `#[shared(...)]` on the struct. The other fields must implement
+/// `Default`.
+#[proc_macro_attribute]
+pub fn injectable(args: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as DeriveInput);
+    injectable::expand(args.into(), item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
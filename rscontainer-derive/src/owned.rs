@@ -0,0 +1,84 @@
+//! Implementation of `#[derive(IOwned)]`.
+
+use crate::fields;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, ExprPath, LitStr, Result, Type};
+
+/// The attributes on the struct.
+struct Attributes {
+    /// `#[owned(params = "Type")]`, defaults to `()`.
+    params: Type,
+    /// `#[owned(error = "Type")]`, defaults to `Infallible`.
+    error: Type,
+    /// `#[owned(constructor = "path")]`.
+    constructor: Option<ExprPath>,
+}
+
+/// Generates the `IOwned` impl.
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let attrs = attributes(&input)?;
+    let params = &attrs.params;
+    let error = &attrs.error;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "IOwned can only be derived for structs",
+            ))
+        }
+    };
+
+    let body = match &attrs.constructor {
+        Some(constructor) => quote! { #constructor(resolver, params) },
+        None => {
+            let instance = fields::instance(fields)?;
+            quote! { ::std::result::Result::Ok(#instance) }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::rscontainer::IOwned for #name #ty_generics #where_clause {
+            type Instance = Self;
+            type Parameters = #params;
+            type Error = #error;
+
+            #[allow(unused_mut, unused_variables)]
+            fn construct(
+                mut resolver: ::rscontainer::Resolver,
+                params: Self::Parameters,
+            ) -> ::std::result::Result<Self::Instance, Self::Error> {
+                #body
+            }
+        }
+    })
+}
+
+/// Parses the `#[owned(...)]` attributes on the struct.
+fn attributes(input: &DeriveInput) -> Result<Attributes> {
+    let mut attrs = Attributes {
+        params: syn::parse_quote!(()),
+        error: syn::parse_quote!(::std::convert::Infallible),
+        constructor: None,
+    };
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("owned")) {
+        attr.parse_nested_meta(|meta| {
+            let value = || meta.value()?.parse::<LitStr>();
+            if meta.path.is_ident("params") {
+                attrs.params = value()?.parse()?;
+            } else if meta.path.is_ident("error") {
+                attrs.error = value()?.parse()?;
+            } else if meta.path.is_ident("constructor") {
+                attrs.constructor = Some(value()?.parse()?);
+            } else {
+                return Err(meta.error("unsupported owned attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
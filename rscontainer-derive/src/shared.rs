@@ -0,0 +1,117 @@
+//! Implementation of `#[derive(IShared)]`.
+
+use crate::fields;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Error, ExprPath, GenericArgument, Ident, LitStr, PathArguments, Result, Type,
+};
+
+/// The attributes on the struct.
+struct Attributes {
+    /// `#[shared(error = "Type")]`, defaults to `Infallible`.
+    error: Type,
+    /// `#[shared(pointer = "Type")]`, defaults to `Arc<Mutex<Self>>`.
+    pointer: Type,
+    /// `#[shared(constructor = "path")]`.
+    constructor: Option<ExprPath>,
+}
+
+/// Generates the `IShared` impl.
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let attrs = attributes(&input)?;
+    let error = &attrs.error;
+    let pointer = &attrs.pointer;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "IShared can only be derived for structs",
+            ))
+        }
+    };
+
+    let body = match &attrs.constructor {
+        Some(constructor) => quote! { #constructor(resolver) },
+        None => {
+            let instance = fields::instance(fields)?;
+            let pointer = wrap(pointer, name, quote! { instance })?;
+            quote! {
+                let instance = #instance;
+                ::std::result::Result::Ok(#pointer)
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::rscontainer::IShared for #name #ty_generics #where_clause {
+            type Pointer = #pointer;
+            type Target = Self;
+            type Error = #error;
+
+            #[allow(unused_mut, unused_variables)]
+            fn construct(
+                mut resolver: ::rscontainer::Resolver,
+            ) -> ::std::result::Result<Self::Pointer, Self::Error> {
+                #body
+            }
+        }
+    })
+}
+
+/// Parses the `#[shared(...)]` attributes on the struct.
+fn attributes(input: &DeriveInput) -> Result<Attributes> {
+    let mut attrs = Attributes {
+        error: syn::parse_quote!(::std::convert::Infallible),
+        pointer: syn::parse_quote!(::std::sync::Arc<::std::sync::Mutex<Self>>),
+        constructor: None,
+    };
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("shared")) {
+        attr.parse_nested_meta(|meta| {
+            let value = || meta.value()?.parse::<LitStr>();
+            if meta.path.is_ident("error") {
+                attrs.error = value()?.parse()?;
+            } else if meta.path.is_ident("pointer") {
+                attrs.pointer = value()?.parse()?;
+            } else if meta.path.is_ident("constructor") {
+                attrs.constructor = Some(value()?.parse()?);
+            } else {
+                return Err(meta.error("unsupported shared attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// Wraps `value` in the pointer type, by calling `new` on every layer, so
+/// `Rc<RefCell<Self>>` becomes `Rc::new(RefCell::new(value))`.
+fn wrap(pointer: &Type, name: &Ident, value: TokenStream) -> Result<TokenStream> {
+    if let Type::Path(ty) = pointer {
+        if ty.path.is_ident("Self") || ty.path.is_ident(name) {
+            return Ok(value);
+        }
+
+        let mut path = ty.path.clone();
+        if let Some(last) = path.segments.last_mut() {
+            if let PathArguments::AngleBracketed(args) = &last.arguments {
+                if let (1, Some(GenericArgument::Type(inner))) =
+                    (args.args.len(), args.args.first())
+                {
+                    let inner = wrap(inner, name, value)?;
+                    last.arguments = PathArguments::None;
+                    return Ok(quote! { #path::new(#inner) });
+                }
+            }
+        }
+    }
+
+    Err(Error::new_spanned(
+        pointer,
+        "can't construct this pointer, use `#[shared(constructor = \"...\")]`",
+    ))
+}
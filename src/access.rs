@@ -1,9 +1,12 @@
 //! Access to the data of services.
 
-use std::cell::{Cell, RefCell};
+use std::cell::{Cell, LazyCell, OnceCell, RefCell};
 use std::ops::Deref;
 use std::rc::Rc;
-use std::sync::{Arc, Mutex, RwLock, TryLockError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock, TryLockError};
+use std::time::Duration;
 
 ///////////////////////////////////////////////////////////////////////////////
 // Poisoning Support
@@ -134,6 +137,207 @@ impl<S> Poisoning<S> {
             Self::Healthy(..) => None
         }
     }
+
+    /// Combines two poisoning statuses into one.
+    ///
+    /// The result is [`Poisoned`] if either of the values is poisoned,
+    /// otherwise it is [`Healthy`].
+    ///
+    /// [`Healthy`]: Poisoning::Healthy
+    /// [`Poisoned`]: Poisoning::Poisoned
+    pub fn zip<T>(self, other: Poisoning<T>) -> Poisoning<(S, T)> {
+        match (self, other) {
+            (Self::Healthy(a), Poisoning::Healthy(b)) => Poisoning::Healthy((a, b)),
+            (Self::Healthy(a), Poisoning::Poisoned(b))
+            | (Self::Poisoned(a), Poisoning::Healthy(b))
+            | (Self::Poisoned(a), Poisoning::Poisoned(b)) => Poisoning::Poisoned((a, b)),
+        }
+    }
+
+    /// Transforms the value, keeping the poisoning status.
+    pub fn map<U, F: FnOnce(S) -> U>(self, f: F) -> Poisoning<U> {
+        match self {
+            Self::Healthy(v) => Poisoning::Healthy(f(v)),
+            Self::Poisoned(v) => Poisoning::Poisoned(f(v)),
+        }
+    }
+
+    /// Transforms the value into another poisoning status.
+    ///
+    /// The result is [`Poisoned`] if either `self` or the result of `f` is
+    /// poisoned, like with [`zip`].
+    ///
+    /// [`Poisoned`]: Poisoning::Poisoned
+    /// [`zip`]: Poisoning::zip
+    pub fn and_then<U, F: FnOnce(S) -> Poisoning<U>>(self, f: F) -> Poisoning<U> {
+        match self {
+            Self::Healthy(v) => f(v),
+            Self::Poisoned(v) => Poisoning::Poisoned(f(v).unpoison()),
+        }
+    }
+
+    /// Recovers a poisoned value with `f`, which makes it [`Healthy`].
+    /// A healthy value is returned as is.
+    ///
+    /// [`Healthy`]: Poisoning::Healthy
+    pub fn or_else<F: FnOnce(S) -> S>(self, f: F) -> Poisoning<S> {
+        match self {
+            Self::Healthy(v) => Self::Healthy(v),
+            Self::Poisoned(v) => Self::Healthy(f(v)),
+        }
+    }
+
+    /// Returns the value if it is not poisoned, otherwise returns `default`.
+    pub fn unwrap_or(self, default: S) -> S {
+        match self {
+            Self::Healthy(v) => v,
+            Self::Poisoned(..) => default,
+        }
+    }
+
+    /// Returns the value if it is not poisoned, otherwise returns the result
+    /// of `f`.
+    pub fn unwrap_or_else<F: FnOnce() -> S>(self, f: F) -> S {
+        match self {
+            Self::Healthy(v) => v,
+            Self::Poisoned(..) => f(),
+        }
+    }
+
+    /// Converts the value into `Ok` if it is not poisoned, and into `Err` if
+    /// it is poisoned.
+    #[inline]
+    pub fn into_result(self) -> Result<S, S> {
+        match self {
+            Self::Healthy(v) => Ok(v),
+            Self::Poisoned(v) => Err(v),
+        }
+    }
+
+    /// Same as [`into_result`], for use with `?` when poisoning is an error.
+    ///
+    /// [`into_result`]: Poisoning::into_result
+    #[inline]
+    pub fn ok_healthy(self) -> Result<S, S> {
+        self.into_result()
+    }
+
+    /// Returns the value if it is not poisoned, otherwise returns `err`.
+    pub fn healthy_or<E>(self, err: E) -> Result<S, E> {
+        match self {
+            Self::Healthy(v) => Ok(v),
+            Self::Poisoned(..) => Err(err),
+        }
+    }
+
+    /// Converts `Ok` into [`Healthy`] and `Err` into [`Poisoned`].
+    ///
+    /// [`Healthy`]: Poisoning::Healthy
+    /// [`Poisoned`]: Poisoning::Poisoned
+    #[inline]
+    pub fn from_result(result: Result<S, S>) -> Self {
+        match result {
+            Ok(v) => Self::Healthy(v),
+            Err(v) => Self::Poisoned(v),
+        }
+    }
+
+    /// Same as [`into_healthy`], named after [`Result::ok`].
+    ///
+    /// [`into_healthy`]: Poisoning::into_healthy
+    #[inline]
+    pub fn ok(self) -> Option<S> {
+        self.into_healthy()
+    }
+
+    /// Same as [`into_poisoned`], named after [`Result::err`].
+    ///
+    /// [`into_poisoned`]: Poisoning::into_poisoned
+    #[inline]
+    pub fn err(self) -> Option<S> {
+        self.into_poisoned()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Timed Access
+///////////////////////////////////////////////////////////////////////////////
+
+/// Repeatedly tries to acquire a lock until `timeout` has passed.
+///
+/// Returns `None` if the lock could not be acquired in time.
+fn try_acquire_for<G>(
+    timeout: Duration,
+    mut try_acquire: impl FnMut() -> std::sync::TryLockResult<G>,
+) -> Option<std::sync::LockResult<G>> {
+    let start = std::time::Instant::now();
+    let mut backoff = Duration::from_micros(1);
+    loop {
+        match try_acquire() {
+            Ok(guard) => return Some(Ok(guard)),
+            Err(TryLockError::Poisoned(poison)) => return Some(Err(poison)),
+            Err(TryLockError::WouldBlock) if start.elapsed() >= timeout => return None,
+            Err(TryLockError::WouldBlock) => {
+                std::thread::sleep(backoff);
+                backoff = (backoff * 2).min(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+/// How long access waits for a lock in debug builds before it panics, unless
+/// the container sets another timeout.
+const DEFAULT_ACCESS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns how long access may wait for a lock: `configured` or
+/// [`DEFAULT_ACCESS_TIMEOUT`] in debug builds, and as long as it takes in
+/// release builds.
+pub(crate) fn access_timeout(configured: Option<Duration>) -> Option<Duration> {
+    if cfg!(debug_assertions) {
+        Some(configured.unwrap_or(DEFAULT_ACCESS_TIMEOUT))
+    } else {
+        None
+    }
+}
+
+/// Gets access to an instance of the service `S` through a closure. With a
+/// timeout, panics if the access doesn't succeed in time, otherwise waits as
+/// long as it takes.
+pub(crate) fn access_within<S, P, U, F>(instance: &P, timeout: Option<Duration>, f: F) -> U
+where
+    S: ?Sized,
+    P: ?Sized + IAccess,
+    F: FnOnce(Poisoning<&P::Target>) -> U,
+{
+    match timeout {
+        Some(timeout) => instance
+            .try_access_for(timeout, f)
+            .unwrap_or_else(|| timed_out::<S>()),
+        None => instance.access(f),
+    }
+}
+
+/// Same as [`access_within()`], for mutable access.
+pub(crate) fn access_mut_within<S, P, U, F>(instance: &P, timeout: Option<Duration>, f: F) -> U
+where
+    S: ?Sized,
+    P: ?Sized + IAccessMut,
+    F: FnOnce(Poisoning<&mut P::Target>) -> U,
+{
+    match timeout {
+        Some(timeout) => instance
+            .try_access_mut_for(timeout, f)
+            .unwrap_or_else(|| timed_out::<S>()),
+        None => instance.access_mut(f),
+    }
+}
+
+/// Panics because accessing an instance of the service `S` timed out.
+fn timed_out<S: ?Sized>() -> ! {
+    panic!(
+        "Timed out while accessing `{}`, this is probably a deadlock",
+        std::any::type_name::<S>()
+    )
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -159,6 +363,20 @@ pub trait IAccess {
     /// The parameter of the closure contains the poisoning status of the
     /// instance.
     fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U;
+
+    /// Tries to get access to the shared instance through a closure, and
+    /// waits at most `timeout` for a lock.
+    ///
+    /// Returns `None` if the access failed. The default implementation
+    /// doesn't wait and calls [`try_access()`](IAccess::try_access).
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        let _ = timeout;
+        self.try_access(f)
+    }
 }
 
 /// Provides mutable access to a shared instance.
@@ -177,6 +395,20 @@ pub trait IAccessMut: IAccess {
     /// The parameter of the closure contains the poisoning status of the
     /// instance.
     fn access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> U;
+
+    /// Tries to get mutable access to the shared instance through a closure,
+    /// and waits at most `timeout` for a lock.
+    ///
+    /// Returns `None` if the access failed. The default implementation
+    /// doesn't wait and calls [`try_access_mut()`](IAccessMut::try_access_mut).
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        let _ = timeout;
+        self.try_access_mut(f)
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -215,6 +447,128 @@ impl<T> Deref for Access<T> {
     }
 }
 
+/// Wrapper that poisons the instance when a closure panics while accessing
+/// it.
+///
+/// `RefCell`, `Cell` and `Access` don't support poisoning, so a panic during
+/// access may leave the instance in an inconsistent state without anyone
+/// noticing. With this wrapper every access after such a panic reports the
+/// instance as [`Poisoned`], just like a `Mutex` does.
+///
+/// [`Poisoned`]: Poisoning::Poisoned
+#[derive(Default, Debug)]
+pub struct PoisonOnPanic<T: ?Sized> {
+    poisoned: AtomicBool,
+    inner: T,
+}
+
+impl<T> PoisonOnPanic<T> {
+    /// Creates a new `PoisonOnPanic` wrapper around some value.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            poisoned: AtomicBool::new(false),
+            inner,
+        }
+    }
+
+    /// Removes the wrapper and returns the original value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ?Sized> PoisonOnPanic<T> {
+    /// Returns true if a closure panicked while accessing the instance.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Marks the instance as healthy again.
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Relaxed);
+    }
+
+    /// Calls the closure and poisons the instance if it panics.
+    fn guard<S, U>(&self, f: impl FnOnce(Poisoning<S>) -> U, poisoning: Poisoning<S>) -> U {
+        let _guard = PoisonGuard {
+            poisoned: &self.poisoned,
+            panicking: std::thread::panicking(),
+        };
+        if self.is_poisoned() {
+            f(Poisoning::Poisoned(poisoning.unpoison()))
+        } else {
+            f(poisoning)
+        }
+    }
+}
+
+/// Wrapper that notifies subscribers each time the instance is mutably
+/// accessed, for example to update a user interface.
+///
+/// Subscribe through the wrapper or with [`Shared::subscribe()`]. A
+/// notification is sent after the closure of `access_mut()` returns, also
+/// when it didn't actually change the instance.
+///
+/// [`Shared::subscribe()`]: crate::Shared::subscribe
+#[derive(Default, Debug)]
+pub struct Observable<T: ?Sized> {
+    subscribers: Mutex<Vec<Sender<()>>>,
+    inner: T,
+}
+
+impl<T> Observable<T> {
+    /// Creates a new `Observable` wrapper around some value.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            subscribers: Mutex::new(Vec::new()),
+            inner,
+        }
+    }
+
+    /// Removes the wrapper and returns the original value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ?Sized> Observable<T> {
+    /// Returns a receiver that gets a message after each mutable access.
+    pub fn subscribe(&self) -> Receiver<()> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers().push(sender);
+        receiver
+    }
+
+    /// Notifies the subscribers, and forgets those that are dropped.
+    fn notify(&self) {
+        self.subscribers().retain(|sender| sender.send(()).is_ok());
+    }
+
+    /// Locks the subscribers. Poisoning is ignored, because the list is
+    /// valid at any point.
+    fn subscribers(&self) -> std::sync::MutexGuard<'_, Vec<Sender<()>>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner())
+    }
+}
+
+/// Sets the poisoned flag when it is dropped during a panic.
+struct PoisonGuard<'a> {
+    poisoned: &'a AtomicBool,
+    /// Whether the thread was already panicking when the guard was created,
+    /// in which case the closure did not cause the panic.
+    panicking: bool,
+}
+
+impl Drop for PoisonGuard<'_> {
+    fn drop(&mut self) {
+        if !self.panicking && std::thread::panicking() {
+            self.poisoned.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // IAccess Implementations
 ///////////////////////////////////////////////////////////////////////////////
@@ -246,7 +600,7 @@ impl<T: ?Sized> IAccess for RefCell<T> {
     }
 }
 
-impl<T: ?Sized + Copy> IAccess for Cell<T> {
+impl<T: Copy> IAccess for Cell<T> {
     type Target = T;
 
     fn try_access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> Option<U> {
@@ -258,6 +612,74 @@ impl<T: ?Sized + Copy> IAccess for Cell<T> {
     }
 }
 
+// OnceCell and OnceLock never poison. They are read-only and initialized
+// with the default value on first access, unless the service set a value
+// before. `try_access` doesn't initialize them, but `try_access_for` does,
+// because there is no lock to wait for. A service that needs another
+// initializer uses LazyCell or LazyLock.
+
+impl<T: Default> IAccess for OnceCell<T> {
+    type Target = T;
+
+    fn try_access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.get().map(|value| f(Poisoning::Healthy(value)))
+    }
+
+    fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
+        f(Poisoning::Healthy(self.get_or_init(T::default)))
+    }
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        _: Duration,
+        f: F,
+    ) -> Option<U> {
+        Some(self.access(f))
+    }
+}
+
+impl<T: Default> IAccess for OnceLock<T> {
+    type Target = T;
+
+    fn try_access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.get().map(|value| f(Poisoning::Healthy(value)))
+    }
+
+    fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
+        f(Poisoning::Healthy(self.get_or_init(T::default)))
+    }
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        _: Duration,
+        f: F,
+    ) -> Option<U> {
+        Some(self.access(f))
+    }
+}
+
+impl<T, F: FnOnce() -> T> IAccess for LazyCell<T, F> {
+    type Target = T;
+
+    fn try_access<U, F2: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F2) -> Option<U> {
+        Some(self.access(f))
+    }
+
+    fn access<U, F2: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F2) -> U {
+        f(Poisoning::Healthy(LazyCell::force(self)))
+    }
+}
+
+impl<T, F: FnOnce() -> T> IAccess for LazyLock<T, F> {
+    type Target = T;
+
+    fn try_access<U, F2: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F2) -> Option<U> {
+        Some(self.access(f))
+    }
+
+    fn access<U, F2: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F2) -> U {
+        f(Poisoning::Healthy(LazyLock::force(self)))
+    }
+}
+
 impl<T: ?Sized> IAccess for Mutex<T> {
     type Target = T;
 
@@ -275,6 +697,17 @@ impl<T: ?Sized> IAccess for Mutex<T> {
             Err(poison) => f(Poisoning::Poisoned(&poison.into_inner())),
         }
     }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        match try_acquire_for(timeout, || self.try_lock())? {
+            Ok(lock) => Some(f(Poisoning::Healthy(&lock))),
+            Err(poison) => Some(f(Poisoning::Poisoned(&poison.into_inner()))),
+        }
+    }
 }
 
 impl<T: ?Sized> IAccess for RwLock<T> {
@@ -294,6 +727,17 @@ impl<T: ?Sized> IAccess for RwLock<T> {
             Err(poison) => f(Poisoning::Poisoned(&poison.into_inner())),
         }
     }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        match try_acquire_for(timeout, || self.try_read())? {
+            Ok(read) => Some(f(Poisoning::Healthy(&read))),
+            Err(poison) => Some(f(Poisoning::Poisoned(&poison.into_inner()))),
+        }
+    }
 }
 
 impl<T: ?Sized + IAccess> IAccess for Rc<T> {
@@ -306,6 +750,14 @@ impl<T: ?Sized + IAccess> IAccess for Rc<T> {
     fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
         self.deref().access(f)
     }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.deref().try_access_for(timeout, f)
+    }
 }
 
 impl<T: ?Sized + IAccess> IAccess for Arc<T> {
@@ -318,6 +770,55 @@ impl<T: ?Sized + IAccess> IAccess for Arc<T> {
     fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
         self.deref().access(f)
     }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.deref().try_access_for(timeout, f)
+    }
+}
+
+impl<T: ?Sized + IAccess> IAccess for PoisonOnPanic<T> {
+    type Target = T::Target;
+
+    fn try_access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.inner.try_access(|instance| self.guard(f, instance))
+    }
+
+    fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
+        self.inner.access(|instance| self.guard(f, instance))
+    }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.inner
+            .try_access_for(timeout, |instance| self.guard(f, instance))
+    }
+}
+
+impl<T: ?Sized + IAccess> IAccess for Observable<T> {
+    type Target = T::Target;
+
+    fn try_access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.inner.try_access(f)
+    }
+
+    fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
+        self.inner.access(f)
+    }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.inner.try_access_for(timeout, f)
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -337,7 +838,7 @@ impl<T: ?Sized> IAccessMut for RefCell<T> {
     }
 }
 
-impl<T: ?Sized + Copy> IAccessMut for Cell<T> {
+impl<T: Copy> IAccessMut for Cell<T> {
     fn try_access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> Option<U> {
         let mut value = self.get();
         let output = f(Poisoning::Healthy(&mut value));
@@ -370,6 +871,17 @@ impl<T: ?Sized> IAccessMut for Mutex<T> {
             Err(poison) => f(Poisoning::Poisoned(&mut poison.into_inner())),
         }
     }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        match try_acquire_for(timeout, || self.try_lock())? {
+            Ok(mut lock) => Some(f(Poisoning::Healthy(&mut lock))),
+            Err(poison) => Some(f(Poisoning::Poisoned(&mut poison.into_inner()))),
+        }
+    }
 }
 
 impl<T: ?Sized> IAccessMut for RwLock<T> {
@@ -389,6 +901,17 @@ impl<T: ?Sized> IAccessMut for RwLock<T> {
             Err(poison) => f(Poisoning::Poisoned(&mut poison.into_inner())),
         }
     }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        match try_acquire_for(timeout, || self.try_write())? {
+            Ok(mut write) => Some(f(Poisoning::Healthy(&mut write))),
+            Err(poison) => Some(f(Poisoning::Poisoned(&mut poison.into_inner()))),
+        }
+    }
 }
 
 impl<T: ?Sized + IAccessMut> IAccessMut for Rc<T> {
@@ -399,6 +922,14 @@ impl<T: ?Sized + IAccessMut> IAccessMut for Rc<T> {
     fn access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> U {
         self.deref().access_mut(f)
     }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.deref().try_access_mut_for(timeout, f)
+    }
 }
 
 impl<T: ?Sized + IAccessMut> IAccessMut for Arc<T> {
@@ -409,6 +940,154 @@ impl<T: ?Sized + IAccessMut> IAccessMut for Arc<T> {
     fn access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> U {
         self.deref().access_mut(f)
     }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.deref().try_access_mut_for(timeout, f)
+    }
+}
+
+impl<T: ?Sized + IAccessMut> IAccessMut for PoisonOnPanic<T> {
+    fn try_access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.inner
+            .try_access_mut(|instance| self.guard(f, instance))
+    }
+
+    fn access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> U {
+        self.inner.access_mut(|instance| self.guard(f, instance))
+    }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.inner
+            .try_access_mut_for(timeout, |instance| self.guard(f, instance))
+    }
+}
+
+impl<T: ?Sized + IAccessMut> IAccessMut for Observable<T> {
+    fn try_access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        let output = self.inner.try_access_mut(f)?;
+        self.notify();
+        Some(output)
+    }
+
+    fn access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> U {
+        let output = self.inner.access_mut(f);
+        self.notify();
+        output
+    }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        let output = self.inner.try_access_mut_for(timeout, f)?;
+        self.notify();
+        Some(output)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// parking_lot
+///////////////////////////////////////////////////////////////////////////////
+
+// parking_lot locks never poison, so the instance is always healthy.
+
+#[cfg(feature = "parking_lot")]
+impl<T: ?Sized> IAccess for parking_lot::Mutex<T> {
+    type Target = T;
+
+    fn try_access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.try_lock().map(|lock| f(Poisoning::Healthy(&lock)))
+    }
+
+    fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
+        let lock = self.lock();
+        f(Poisoning::Healthy(&lock))
+    }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.try_lock_for(timeout)
+            .map(|lock| f(Poisoning::Healthy(&lock)))
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<T: ?Sized> IAccessMut for parking_lot::Mutex<T> {
+    fn try_access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.try_lock()
+            .map(|mut lock| f(Poisoning::Healthy(&mut lock)))
+    }
+
+    fn access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> U {
+        let mut lock = self.lock();
+        f(Poisoning::Healthy(&mut lock))
+    }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.try_lock_for(timeout)
+            .map(|mut lock| f(Poisoning::Healthy(&mut lock)))
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<T: ?Sized> IAccess for parking_lot::RwLock<T> {
+    type Target = T;
+
+    fn try_access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.try_read().map(|read| f(Poisoning::Healthy(&read)))
+    }
+
+    fn access<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(&self, f: F) -> U {
+        let read = self.read();
+        f(Poisoning::Healthy(&read))
+    }
+
+    fn try_access_for<U, F: FnOnce(Poisoning<&Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.try_read_for(timeout)
+            .map(|read| f(Poisoning::Healthy(&read)))
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<T: ?Sized> IAccessMut for parking_lot::RwLock<T> {
+    fn try_access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> Option<U> {
+        self.try_write()
+            .map(|mut write| f(Poisoning::Healthy(&mut write)))
+    }
+
+    fn access_mut<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(&self, f: F) -> U {
+        let mut write = self.write();
+        f(Poisoning::Healthy(&mut write))
+    }
+
+    fn try_access_mut_for<U, F: FnOnce(Poisoning<&mut Self::Target>) -> U>(
+        &self,
+        timeout: Duration,
+        f: F,
+    ) -> Option<U> {
+        self.try_write_for(timeout)
+            .map(|mut write| f(Poisoning::Healthy(&mut write)))
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -449,21 +1128,218 @@ mod tests {
     fn poisoning_is_poisoned() {
         let poison = Poisoning::Healthy(321);
         let is_poisoned = poison.is_poisoned();
-        assert_eq!(is_poisoned, false);
+        assert!(!is_poisoned);
 
         let poison = Poisoning::Poisoned(123);
         let is_poisoned = poison.is_poisoned();
-        assert_eq!(is_poisoned, true);
+        assert!(is_poisoned);
     }
 
     #[test]
     fn poisoning_is_healthy() {
         let poison = Poisoning::Healthy(321);
         let is_poisoned = poison.is_healthy();
-        assert_eq!(is_poisoned, true);
+        assert!(is_poisoned);
 
         let poison = Poisoning::Poisoned(123);
         let is_poisoned = poison.is_healthy();
-        assert_eq!(is_poisoned, false);
+        assert!(!is_poisoned);
+    }
+
+    #[test]
+    fn poisoning_zip() {
+        let zipped = Poisoning::Healthy(1).zip(Poisoning::Healthy('a'));
+        assert_eq!(zipped, Poisoning::Healthy((1, 'a')));
+
+        let zipped = Poisoning::Healthy(1).zip(Poisoning::Poisoned('a'));
+        assert_eq!(zipped, Poisoning::Poisoned((1, 'a')));
+
+        let zipped = Poisoning::Poisoned(1).zip(Poisoning::Healthy('a'));
+        assert_eq!(zipped, Poisoning::Poisoned((1, 'a')));
+
+        let zipped = Poisoning::Poisoned(1).zip(Poisoning::Poisoned('a'));
+        assert_eq!(zipped, Poisoning::Poisoned((1, 'a')));
+    }
+
+    #[test]
+    fn poisoning_map() {
+        assert_eq!(Poisoning::Healthy(1).map(|v| v * 2), Poisoning::Healthy(2));
+        assert_eq!(
+            Poisoning::Poisoned(1).map(|v| v * 2),
+            Poisoning::Poisoned(2)
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn poisoning_and_then() {
+        let chained = Poisoning::Healthy(1).and_then(|v| Poisoning::Healthy(v + 1));
+        assert_eq!(chained, Poisoning::Healthy(2));
+
+        let chained = Poisoning::Healthy(1).and_then(|v| Poisoning::Poisoned(v + 1));
+        assert_eq!(chained, Poisoning::Poisoned(2));
+
+        let chained = Poisoning::Poisoned(1).and_then(|v| Poisoning::Healthy(v + 1));
+        assert_eq!(chained, Poisoning::Poisoned(2));
+    }
+
+    #[test]
+    fn poisoning_or_else() {
+        assert_eq!(Poisoning::Healthy(1).or_else(|_| 0), Poisoning::Healthy(1));
+        assert_eq!(Poisoning::Poisoned(1).or_else(|_| 0), Poisoning::Healthy(0));
+    }
+
+    #[test]
+    fn poisoning_unwrap_or() {
+        assert_eq!(Poisoning::Healthy(1).unwrap_or(0), 1);
+        assert_eq!(Poisoning::Poisoned(1).unwrap_or(0), 0);
+        assert_eq!(Poisoning::Healthy(1).unwrap_or_else(|| 0), 1);
+        assert_eq!(Poisoning::Poisoned(1).unwrap_or_else(|| 0), 0);
+    }
+
+    #[test]
+    fn poisoning_result() {
+        assert_eq!(Poisoning::Healthy(1).into_result(), Ok(1));
+        assert_eq!(Poisoning::Poisoned(1).into_result(), Err(1));
+        assert_eq!(Poisoning::from_result(Ok(1)), Poisoning::Healthy(1));
+        assert_eq!(Poisoning::from_result(Err(1)), Poisoning::Poisoned(1));
+
+        assert_eq!(Poisoning::Healthy(1).ok(), Some(1));
+        assert_eq!(Poisoning::Poisoned(1).ok(), None);
+        assert_eq!(Poisoning::Healthy(1).err(), None);
+        assert_eq!(Poisoning::Poisoned(1).err(), Some(1));
+    }
+
+    #[test]
+    fn poisoning_healthy_or() {
+        assert_eq!(Poisoning::Healthy(1).ok_healthy(), Ok(1));
+        assert_eq!(Poisoning::Poisoned(1).ok_healthy(), Err(1));
+        assert_eq!(Poisoning::Healthy(1).healthy_or("poisoned"), Ok(1));
+        assert_eq!(
+            Poisoning::Poisoned(1).healthy_or("poisoned"),
+            Err("poisoned")
+        );
+    }
+
+    #[test]
+    fn poison_on_panic() {
+        let instance = Rc::new(PoisonOnPanic::new(RefCell::new(1u32)));
+        instance.access(|value| assert!(value.is_healthy()));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            instance.access_mut(|value| {
+                *value.assert_healthy() = 2;
+                panic!("inconsistent state");
+            })
+        }));
+        assert!(result.is_err());
+        assert!(instance.is_poisoned());
+        instance.access(|value| assert_eq!(*value.assert_poisoned(), 2));
+        instance.try_access_mut(|value| assert!(value.is_poisoned()));
+
+        instance.clear_poison();
+        instance.access(|value| assert!(value.is_healthy()));
+    }
+
+    #[test]
+    #[cfg(feature = "parking_lot")]
+    fn parking_lot() {
+        let mutex = Arc::new(parking_lot::Mutex::new(1));
+        mutex.access_mut(|n| *n.assert_healthy() += 1);
+        assert_eq!(mutex.access(|n| *n.assert_healthy()), 2);
+
+        let guard = mutex.lock();
+        assert_eq!(mutex.try_access(|n| *n.assert_healthy()), None);
+        drop(guard);
+
+        let rwlock = Arc::new(parking_lot::RwLock::new(1));
+        rwlock.access_mut(|n| *n.assert_healthy() += 1);
+        let read = rwlock.read();
+        assert_eq!(rwlock.try_access(|n| *n.assert_healthy()), Some(2));
+        assert_eq!(rwlock.try_access_mut(|n| *n.assert_healthy()), None);
+        drop(read);
+    }
+
+    #[test]
+    fn observable() {
+        let observable = Observable::new(RefCell::new(1));
+        let first = observable.subscribe();
+        let second = observable.subscribe();
+
+        assert!(observable
+            .try_access_mut(|n| *n.assert_healthy() = 2)
+            .is_some());
+        assert!(first.try_recv().is_ok());
+        assert!(second.try_recv().is_ok());
+
+        drop(first);
+        observable.access_mut(|n| *n.assert_healthy() = 3);
+        assert_eq!(observable.subscribers().len(), 1);
+        assert!(second.try_recv().is_ok());
+        assert_eq!(observable.into_inner().into_inner(), 3);
+    }
+
+    #[test]
+    fn try_access_for() {
+        let mutex = Arc::new(Mutex::new(1));
+        let lock = mutex.lock().unwrap();
+        let timeout = Duration::from_millis(10);
+        assert!(mutex.try_access_for(timeout, |_| ()).is_none());
+        drop(lock);
+        assert_eq!(
+            mutex.try_access_for(timeout, |n| *n.assert_healthy()),
+            Some(1)
+        );
+
+        let rwlock = RwLock::new(2);
+        let write = rwlock.write().unwrap();
+        assert!(rwlock.try_access_for(timeout, |_| ()).is_none());
+        drop(write);
+        assert_eq!(
+            rwlock.try_access_for(timeout, |n| *n.assert_healthy()),
+            Some(2)
+        );
+
+        let cell = RefCell::new(3);
+        assert_eq!(
+            cell.try_access_for(timeout, |n| *n.assert_healthy()),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn once() {
+        let lock = Arc::new(OnceLock::new());
+        assert!(lock.try_access(|_| ()).is_none());
+        lock.set(5).unwrap();
+        assert_eq!(lock.access(|n| *n.assert_healthy()), 5);
+        assert_eq!(lock.try_access(|n| *n.assert_healthy()), Some(5));
+
+        let cell = Rc::new(OnceCell::new());
+        assert!(cell.try_access(|_| ()).is_none());
+        cell.set("value").unwrap();
+        assert_eq!(cell.access(|s| *s.assert_healthy()), "value");
+    }
+
+    #[test]
+    fn once_default() {
+        let lock = OnceLock::<u32>::new();
+        assert_eq!(lock.access(|n| *n.assert_healthy()), 0);
+        assert_eq!(lock.try_access(|n| *n.assert_healthy()), Some(0));
+
+        let cell = OnceCell::<String>::new();
+        assert!(cell.try_access(|_| ()).is_none());
+        let timeout = Duration::ZERO;
+        assert_eq!(cell.try_access_for(timeout, |s| s.assert_healthy().len()), Some(0));
+        assert_eq!(cell.get().map(String::as_str), Some(""));
+    }
+
+    #[test]
+    fn lazy() {
+        let lock = Arc::new(LazyLock::new(|| 5));
+        assert_eq!(lock.try_access(|n| *n.assert_healthy()), Some(5));
+        assert_eq!(lock.access(|n| *n.assert_healthy()), 5);
+
+        let cell = Rc::new(LazyCell::new(|| "value"));
+        assert_eq!(cell.access(|s| *s.assert_healthy()), "value");
+    }
+}
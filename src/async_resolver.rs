@@ -0,0 +1,175 @@
+//! Resolver for services that are constructed asynchronously.
+
+use crate::service_traits::{IAsyncOwned, IAsyncShared};
+use crate::{Resolver, ServiceContainer};
+
+/// Used to resolve asynchronous services from the service container.
+///
+/// Asynchronous services can only be resolved with this resolver. The
+/// normal [`Resolver`] never blocks on an asynchronous constructor.
+///
+/// The resolver borrows the container mutably for as long as it lives, also
+/// across await points. Therefore services are resolved one at a time and a
+/// shared instance is never constructed twice. Because the container is not
+/// `Send`, neither are the futures that hold the resolver. They must be run
+/// on the current thread, for example with `block_on` or on a local task
+/// set, and can't be spawned onto a multi-threaded runtime.
+#[derive(Debug)]
+pub struct AsyncResolver<'ctn> {
+    ctn: &'ctn mut ServiceContainer,
+}
+
+impl<'ctn> AsyncResolver<'ctn> {
+    /// Creates a new async resolver.
+    ///
+    /// Just like the normal resolver, this must be `pub(crate)`.
+    pub(crate) fn new(ctn: &'ctn mut ServiceContainer) -> Self {
+        Self { ctn }
+    }
+
+    /// Returns a normal resolver, to resolve synchronous services from
+    /// within an asynchronous constructor.
+    pub fn resolver(&mut self) -> Resolver<'_> {
+        self.ctn.resolver()
+    }
+
+    /// Resolves an asynchronous shared instance.
+    ///
+    /// The first time the service is resolved it is constructed and stored
+    /// in the container, every other time the stored pointer is cloned. The
+    /// instance is accessed through [`IAccess`] on the pointer.
+    ///
+    /// [`IAccess`]: crate::internals::IAccess
+    pub async fn shared<S: ?Sized + IAsyncShared + 'static>(
+        &mut self,
+    ) -> Result<S::Pointer, S::Error> {
+        if let Some(instance) = self.ctn.get_async_shared::<S>() {
+            return Ok(instance);
+        }
+
+        let instance = S::construct(AsyncResolver::new(self.ctn)).await?;
+        self.ctn.store_async::<S>(instance.clone());
+        Ok(instance)
+    }
+
+    /// Resolves an asynchronous owned instance.
+    pub async fn owned<S: ?Sized + IAsyncOwned + 'static>(
+        &mut self,
+        params: S::Parameters,
+    ) -> Result<S::Instance, S::Error> {
+        S::construct(AsyncResolver::new(self.ctn), params).await
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::IAccess;
+    use crate::service_traits::ConstructFuture;
+    use crate::Access;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    /// Polls a future to completion on the current thread, which is parked
+    /// while the future is pending.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// A future that is pending the first time it is polled.
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    struct Config;
+
+    impl IAsyncShared for Config {
+        type Pointer = Rc<Access<u32>>;
+        type Target = u32;
+        type Error = ();
+
+        fn construct(_: AsyncResolver<'_>) -> ConstructFuture<'_, Self::Pointer, Self::Error> {
+            Box::pin(async {
+                YieldNow(false).await;
+                Ok(Rc::new(Access::new(80)))
+            })
+        }
+    }
+
+    struct Connection(u32);
+
+    impl IAsyncOwned for Connection {
+        type Instance = Connection;
+        type Parameters = u32;
+        type Error = ();
+
+        fn construct(
+            mut ctn: AsyncResolver<'_>,
+            offset: u32,
+        ) -> ConstructFuture<'_, Self::Instance, Self::Error> {
+            Box::pin(async move {
+                let config = ctn.shared::<Config>().await?;
+                let port = config.access(|port| *port.assert_healthy());
+                Ok(Connection(port + offset))
+            })
+        }
+    }
+
+    #[test]
+    fn resolve_async_shared() {
+        let mut ctn = ServiceContainer::new();
+        let mut resolver = ctn.async_resolver();
+        let config = block_on(resolver.shared::<Config>()).unwrap();
+        let config_2 = block_on(resolver.shared::<Config>()).unwrap();
+        assert!(Rc::ptr_eq(&config, &config_2));
+        assert_eq!(config.access(|port| *port.assert_healthy()), 80);
+
+        let types: Vec<_> = ctn.registered_types().collect();
+        assert_eq!(types, vec![std::any::TypeId::of::<Config>()]);
+        assert_eq!(ctn.describe()[0].name, std::any::type_name::<Config>());
+        assert_eq!(ctn.count_constructed(), 1);
+    }
+
+    #[test]
+    fn resolve_async_owned() {
+        let mut ctn = ServiceContainer::new();
+        let connection = block_on(ctn.async_resolver().owned::<Connection>(8000)).unwrap();
+        assert_eq!(connection.0, 8080);
+    }
+}
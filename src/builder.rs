@@ -1,16 +1,86 @@
 //! Create a container with the builder pattern.
 
 use crate::container::ServiceContainer;
+use crate::error::{BuildError, ValidationError};
 use crate::getters::Shared;
-use crate::internal_helpers::{OwnedCtor, SharedCtor, SharedPtr, TypeErasedService};
-use crate::service_traits::{IOwned, IShared};
-use fnv::FnvHashMap;
-use std::any::TypeId;
+use crate::internal_helpers::{named_key, service_key, ServiceKey};
+use crate::internal_helpers::{OwnedPool, PointerType, SharedPtr, TypeErasedService};
+use crate::pointers::ISharedPointer;
+use crate::scope::ScopedContainer;
+use crate::service_traits::{Dependency, IDispose, IOwned, IShared};
+use crate::Resolver;
+use fnv::{FnvHashMap, FnvHashSet};
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::time::Duration;
+
+/// What to do when a service is registered in both containers that are
+/// merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the registration of the container that is merged into.
+    KeepFirst,
+    /// Keep the registration of the container that is merged from.
+    KeepLast,
+    /// Panic, because a service should only be registered once.
+    Panic,
+}
+
+/// Moves the services of `from` into `into`, see [`MergeStrategy`].
+///
+/// A service is merged as a whole: its instance and constructors are never
+/// mixed from both sides. The discarded entry is dropped, which releases
+/// its pointers once.
+fn merge_services(
+    into: &mut FnvHashMap<ServiceKey, TypeErasedService>,
+    from: FnvHashMap<ServiceKey, TypeErasedService>,
+    strategy: MergeStrategy,
+) {
+    for (key, service) in from {
+        match into.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(service);
+            }
+            Entry::Occupied(mut entry) => match strategy {
+                MergeStrategy::KeepFirst => {}
+                MergeStrategy::KeepLast => {
+                    entry.insert(service);
+                }
+                MergeStrategy::Panic => {
+                    panic!(
+                        "Service `{}` is registered in both containers",
+                        service.name
+                    )
+                }
+            },
+        }
+    }
+}
+
+/// A group of registrations, for example of one subsystem, that can keep
+/// state such as configuration.
+///
+/// Modules without state can be plain functions, see
+/// [`ContainerBuilder::with_modules()`].
+pub trait Module {
+    /// Adds the registrations of the module to the builder.
+    fn register(self, builder: ContainerBuilder) -> ContainerBuilder;
+}
+
+/// The default capacity of the stacks that track the services that are
+/// being constructed.
+const DEFAULT_RESOLVE_STACK_CAPACITY: usize = 8;
 
 /// Create a container with the builder pattern.
+#[derive(Debug)]
 pub struct ContainerBuilder {
     /// The services in the container.
-    services: FnvHashMap<TypeId, TypeErasedService>,
+    services: FnvHashMap<ServiceKey, TypeErasedService>,
+    /// The initial capacity of the resolve stacks of the container.
+    resolve_stack_capacity: usize,
+    /// How long access may wait for a lock in debug builds.
+    access_timeout: Option<Duration>,
 }
 
 impl ContainerBuilder {
@@ -18,6 +88,8 @@ impl ContainerBuilder {
     pub fn new() -> Self {
         Self {
             services: FnvHashMap::default(),
+            resolve_stack_capacity: DEFAULT_RESOLVE_STACK_CAPACITY,
+            access_timeout: None,
         }
     }
 
@@ -25,61 +97,354 @@ impl ContainerBuilder {
     pub fn with_capacity(capacity: usize) -> Self {
         ContainerBuilder {
             services: FnvHashMap::with_capacity_and_hasher(capacity, Default::default()),
+            resolve_stack_capacity: DEFAULT_RESOLVE_STACK_CAPACITY,
+            access_timeout: None,
         }
     }
 
     /// Returns the inner hashmap for testing purposes.
     #[cfg(test)]
     #[allow(unused)]
-    fn inner(&self) -> &FnvHashMap<TypeId, TypeErasedService> {
+    fn inner(&self) -> &FnvHashMap<ServiceKey, TypeErasedService> {
         &self.services
     }
 
     /// Returns an entry in the service container.
-    fn entry(&mut self, key: TypeId) -> &mut TypeErasedService {
-        self.services.entry(key).or_default()
+    fn entry<S: 'static + ?Sized>(&mut self) -> &mut TypeErasedService {
+        self.entry_at::<S>(service_key::<S>())
+    }
+
+    /// Returns the entry of the service `S` with the given key.
+    fn entry_at<S: 'static + ?Sized>(&mut self, key: ServiceKey) -> &mut TypeErasedService {
+        self.services
+            .entry(key)
+            .or_insert_with(TypeErasedService::of::<S>)
     }
 
     /// Inserts a shared instance.
     pub fn with_shared<S: 'static + ?Sized + IShared>(mut self, shared: Shared<S>) -> Self {
-        self.entry(TypeId::of::<S>()).shared_ptr = Some(SharedPtr::new(shared.into_inner()));
+        self.entry::<S>().set_shared::<S>(shared.into_inner());
+        self
+    }
+
+    /// Inserts a named shared instance, next to the unnamed instance and the
+    /// instances with other names.
+    pub fn with_named_shared<S: 'static + ?Sized + IShared>(
+        mut self,
+        name: &str,
+        shared: Shared<S>,
+    ) -> Self {
+        self.entry_at::<S>(named_key::<S>(name))
+            .set_shared::<S>(shared.into_inner());
+        self
+    }
+
+    /// Sets a custom constructor for a named shared instance.
+    pub fn with_named_shared_constructor<S: 'static + ?Sized + IShared>(
+        mut self,
+        name: &str,
+        ctor: impl Fn(Resolver) -> Result<S::Pointer, S::Error> + 'static,
+    ) -> Self {
+        self.entry_at::<S>(named_key::<S>(name))
+            .set_shared_ctor::<S>(ctor);
         self
     }
 
     /// Sets a custom constructor for a shared instance.
+    ///
+    /// The constructor can be a closure that captures its environment.
     pub fn with_shared_constructor<S: 'static + ?Sized + IShared>(
         mut self,
-        ctor: SharedCtor<S>,
+        ctor: impl Fn(Resolver) -> Result<S::Pointer, S::Error> + 'static,
     ) -> Self {
-        self.entry(TypeId::of::<S>()).shared_ctor = Some(unsafe { std::mem::transmute(ctor) });
+        self.entry::<S>().set_shared_ctor::<S>(ctor);
+        self
+    }
+
+    /// Sets a custom constructor for a shared instance, and checks that the
+    /// pointer it returns is the pointer of the service.
+    ///
+    /// The pointer type `P` is recorded for
+    /// [`ServiceContainer::validate()`], which reports it if it's not
+    /// `S::Pointer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if `P` is not `S::Pointer`. In release builds
+    /// the constructor is registered anyway, and resolving the service
+    /// panics instead.
+    pub fn with_shared_constructor_checked<S, P>(
+        mut self,
+        ctor: impl Fn(Resolver) -> Result<P, S::Error> + 'static,
+    ) -> Self
+    where
+        S: 'static + ?Sized + IShared,
+        S::Pointer: 'static,
+        P: ISharedPointer + 'static,
+    {
+        let matches = TypeId::of::<P>() == TypeId::of::<S::Pointer>();
+        debug_assert!(matches, "{}", pointer_mismatch::<S, P>());
+
+        let entry = self.entry::<S>();
+        entry.pointer.get_or_insert(PointerType::of::<P>());
+        entry.set_shared_ctor::<S>(move |ctn| {
+            assert!(matches, "{}", pointer_mismatch::<S, P>());
+            let mut pointer = Some(ctor(ctn)?);
+            let pointer = (&mut pointer as &mut dyn Any)
+                .downcast_mut::<Option<S::Pointer>>()
+                .and_then(Option::take);
+            Ok(pointer.expect("the pointer types are equal"))
+        });
+        self
+    }
+
+    /// Sets a custom constructor for a shared instance that may mutate its
+    /// captured state, for example to count or cache something.
+    pub fn with_shared_factory<S, F>(self, factory: F) -> Self
+    where
+        S: 'static + ?Sized + IShared,
+        F: FnMut(Resolver) -> Result<S::Pointer, S::Error> + 'static,
+    {
+        // The factory can't be called re-entrantly, because a circular
+        // dependency panics before the constructor is called again.
+        let factory = RefCell::new(factory);
+        self.with_shared_constructor::<S>(move |ctn| (factory.borrow_mut())(ctn))
+    }
+
+    /// Sets a fallback that is called with the error when the constructor of
+    /// a shared instance fails, for example to use a degraded instance.
+    ///
+    /// The instance of the fallback is stored like a normal instance. If the
+    /// fallback fails too, its error is returned.
+    pub fn with_shared_fallback<S: 'static + ?Sized + IShared>(
+        mut self,
+        fallback: impl Fn(S::Error, Resolver) -> Result<S::Pointer, S::Error> + 'static,
+    ) -> Self {
+        self.entry::<S>().set_shared_fallback::<S>(fallback);
+        self
+    }
+
+    /// Tries the constructor of a shared instance up to `attempts` times,
+    /// and waits `backoff` after each failure, for example for services that
+    /// connect over a network.
+    ///
+    /// Only a successful instance is stored. If every attempt fails, the last
+    /// error is passed to the fallback, or returned if there is none.
+    pub fn with_shared_retry<S: 'static + ?Sized + IShared>(
+        mut self,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Self {
+        self.entry::<S>().set_shared_retry::<S>(attempts, backoff);
         self
     }
 
     /// Sets a custom constructor for an owned instance.
+    ///
+    /// The constructor can be a closure that captures its environment.
     pub fn with_owned_constructor<S: 'static + ?Sized + IOwned>(
         mut self,
-        ctor: OwnedCtor<S>,
+        ctor: impl Fn(Resolver, S::Parameters) -> Result<S::Instance, S::Error> + 'static,
     ) -> Self {
-        self.entry(TypeId::of::<S>()).owned_ctor = Some(unsafe { std::mem::transmute(ctor) });
+        self.entry::<S>().set_owned_ctor::<S>(ctor);
+        self
+    }
+
+    /// Enables pooling of owned instances of `S`.
+    ///
+    /// Instances that are given back with
+    /// [`ServiceContainer::release_owned()`] are handed out again by
+    /// [`Resolver::owned_pooled()`] instead of constructing a new one. The
+    /// parameters are ignored when an instance is reused, so only enable
+    /// this for services that don't depend on them.
+    pub fn with_owned_pool<S: 'static + ?Sized + IOwned>(mut self) -> Self {
+        self.entry::<S>().owned_pool = Some(SharedPtr::new(OwnedPool::<S>::default()));
+        self
+    }
+
+    /// Registers `S` to be shut down by [`ServiceContainer::dispose_all()`].
+    pub fn with_dispose<S>(mut self) -> Self
+    where
+        S: 'static + ?Sized + IDispose,
+        S::Error: 'static,
+    {
+        self.entry::<S>().set_disposer::<S>();
         self
     }
 
     /// Sets custom contructors for an owned and shared intance.
     pub fn with_constructors<S: 'static + ?Sized + IOwned + IShared>(
         mut self,
-        owned: OwnedCtor<S>,
-        shared: SharedCtor<S>,
+        owned: impl Fn(Resolver, <S as IOwned>::Parameters) -> Result<S::Instance, <S as IOwned>::Error>
+            + 'static,
+        shared: impl Fn(Resolver) -> Result<S::Pointer, <S as IShared>::Error> + 'static,
     ) -> Self {
-        let mut entry = self.entry(TypeId::of::<S>());
-        entry.shared_ctor = Some(unsafe { std::mem::transmute(shared) });
-        entry.owned_ctor = Some(unsafe { std::mem::transmute(owned) });
+        let entry = self.entry::<S>();
+        entry.set_shared_ctor::<S>(shared);
+        entry.set_owned_ctor::<S>(owned);
+        self
+    }
+
+    /// Adds the registrations of another builder, for example one from a
+    /// different subsystem.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a service is registered in both builders and the strategy is
+    /// [`MergeStrategy::Panic`].
+    pub fn merge(mut self, other: ContainerBuilder, strategy: MergeStrategy) -> Self {
+        merge_services(&mut self.services, other.services, strategy);
+        self
+    }
+
+    /// Adds the registrations of a [`Module`].
+    pub fn with_module<M: Module>(self, module: M) -> Self {
+        module.register(self)
+    }
+
+    /// Applies registration functions in order, so a later module can
+    /// override the registrations of an earlier one.
+    pub fn with_modules(self, modules: &[fn(ContainerBuilder) -> ContainerBuilder]) -> Self {
+        modules.iter().fold(self, |builder, module| module(builder))
+    }
+
+    /// Calls a closure with a reference to the builder and returns the
+    /// builder, to debug the registrations without breaking the chain.
+    pub fn inspect<F: FnOnce(&Self)>(self, f: F) -> Self {
+        f(&self);
+        self
+    }
+
+    /// Sets the initial capacity of the stacks that track the services that
+    /// are being constructed, to avoid reallocations during deep
+    /// resolutions. Defaults to 8.
+    pub fn with_resolve_stack_capacity(mut self, capacity: usize) -> Self {
+        self.resolve_stack_capacity = capacity;
+        self
+    }
+
+    /// Sets how long access to a shared instance of the container may wait
+    /// for a lock in debug builds, instead of 30 seconds.
+    ///
+    /// In debug builds, [`Shared::access()`], [`Resolver::with_shared()`] and
+    /// the other blocking accessors panic with the type name of the service
+    /// when they wait longer than the timeout, to turn a deadlock into a
+    /// panic. Release builds wait as long as it takes. Child scopes use the
+    /// timeout of their parent.
+    pub fn with_access_timeout(mut self, timeout: Duration) -> Self {
+        self.access_timeout = Some(timeout);
         self
     }
 
     /// Builds the container.
     pub fn build(self) -> ServiceContainer {
-        ServiceContainer::new_built(self.services)
+        let mut ctn = ServiceContainer::new_built(self.services);
+        ctn.reserve_resolve_stacks(self.resolve_stack_capacity);
+        ctn.set_access_timeout(self.access_timeout);
+        ctn
+    }
+
+    /// Constructs the shared instance of `S` in [`try_build()`] and
+    /// [`ServiceContainer::preload()`], also when `S` has no custom shared
+    /// constructor.
+    ///
+    /// [`try_build()`]: ContainerBuilder::try_build
+    pub fn with_eager_init<S: 'static + ?Sized + IShared>(mut self) -> Self {
+        self.entry::<S>().set_eager::<S>();
+        self
+    }
+
+    /// Builds the container and constructs its shared instances eagerly, to
+    /// fail at startup instead of at the first resolve.
+    ///
+    /// First checks that the dependencies of every shared service without an
+    /// instance can be resolved. The dependencies are declared with
+    /// [`IShared::dependencies()`]. A dependency can be resolved when it has
+    /// an unnamed shared instance or custom shared constructor in the
+    /// builder, or when it's
+    /// declared with [`Dependency::shared()`] and its own dependencies can
+    /// be resolved, because it's then constructed by default. If one can't
+    /// be resolved, nothing is constructed.
+    ///
+    /// Then constructs every service with a custom shared constructor or
+    /// with [`with_eager_init()`], see [`ServiceContainer::preload()`]. All
+    /// services are tried, and all the errors are returned.
+    ///
+    /// [`with_eager_init()`]: ContainerBuilder::with_eager_init
+    pub fn try_build(self) -> Result<ServiceContainer, BuildError> {
+        let mut errors = Vec::new();
+        let mut checked = FnvHashSet::default();
+        for service in self.services.values() {
+            if service.shared_ptr.is_some() {
+                continue;
+            }
+            if let Some(dependencies) = service.dependencies {
+                self.check_dependencies(service.name, dependencies(), &mut checked, &mut errors);
+            }
+        }
+        if !errors.is_empty() {
+            return Err(BuildError::MissingDependencies(errors));
+        }
+
+        let mut ctn = self.build();
+        ctn.preload().map_err(BuildError::Construction)?;
+        Ok(ctn)
+    }
+
+    /// Checks that the dependencies of `required_by` can be resolved, and
+    /// follows the dependencies that are constructed by default.
+    fn check_dependencies(
+        &self,
+        required_by: &'static str,
+        dependencies: Vec<Dependency>,
+        checked: &mut FnvHashSet<TypeId>,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for dependency in dependencies {
+            let registered = self
+                .services
+                .get(&(dependency.type_id, 0))
+                .is_some_and(|service| {
+                    service.shared_ptr.is_some() || service.shared_ctor.is_some()
+                });
+            if registered {
+                continue;
+            }
+            match dependency.default {
+                Some(dependencies) => {
+                    if checked.insert(dependency.type_id) {
+                        self.check_dependencies(dependency.name, dependencies(), checked, errors);
+                    }
+                }
+                None => errors.push(ValidationError {
+                    type_id: dependency.type_id,
+                    type_name: Some(dependency.name),
+                    required_by,
+                }),
+            }
+        }
     }
+
+    /// Builds a child container of `parent`, see [`ScopedContainer`].
+    pub fn build_child(self, parent: &ServiceContainer) -> ScopedContainer<'_> {
+        ScopedContainer::new(parent, self.services)
+    }
+}
+
+impl Default for ContainerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Describes a checked constructor of `S` that returns a `P`.
+fn pointer_mismatch<S: ?Sized + IShared, P>() -> String {
+    format!(
+        "The constructor of `{}` returns `{}` instead of `{}`",
+        std::any::type_name::<S>(),
+        std::any::type_name::<P>(),
+        std::any::type_name::<S::Pointer>()
+    )
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -99,6 +464,12 @@ mod tests {
         assert_eq!(ctn.inner().capacity(), 0);
     }
 
+    #[test]
+    fn default() {
+        let ctn = ContainerBuilder::default();
+        assert_eq!(ctn.inner().capacity(), 0);
+    }
+
     #[test]
     fn with_capacity() {
         let ctn = ContainerBuilder::with_capacity(50);
@@ -114,7 +485,7 @@ mod tests {
     #[test]
     fn entry() {
         let mut ctn = ContainerBuilder::new();
-        let entry = ctn.entry(TypeId::of::<()>());
+        let entry = ctn.entry::<()>();
 
         assert!(entry.shared_ptr.is_none());
         assert!(entry.shared_ctor.is_none());
@@ -131,7 +502,7 @@ mod tests {
 
         assert_eq!(ctn.inner().len(), 1);
 
-        let entry = ctn.entry(TypeId::of::<u32>());
+        let entry = ctn.entry::<u32>();
 
         assert_eq!(
             Rc::as_ptr(shared_clone.inner()) as *const (),
@@ -150,13 +521,10 @@ mod tests {
         ctn = ctn.with_shared_constructor::<u32>(ctor);
 
         assert_eq!(ctn.inner().len(), 1);
+        assert!(ctn.entry::<u32>().shared_ctor.is_some());
 
-        let entry = ctn.entry(TypeId::of::<u32>());
-
-        assert_eq!(
-            ctor as *const (),
-            *entry.shared_ctor.as_ref().unwrap() as *const ()
-        );
+        let instance: Shared<u32> = ctn.build().resolver().shared().unwrap();
+        assert_eq!(***instance.inner(), 456);
     }
 
     #[test]
@@ -171,12 +539,10 @@ mod tests {
 
         assert_eq!(ctn.inner().len(), 1);
 
-        let entry = ctn.entry(TypeId::of::<u32>());
+        assert!(ctn.entry::<u32>().owned_ctor.is_some());
 
-        assert_eq!(
-            ctor as *const (),
-            *entry.owned_ctor.as_ref().unwrap() as *const ()
-        );
+        let instance = ctn.build().resolver().owned::<u32>(()).unwrap();
+        assert_eq!(instance, 456);
     }
 
     #[test]
@@ -195,16 +561,289 @@ mod tests {
 
         assert_eq!(ctn.inner().len(), 1);
 
-        let entry = ctn.entry(TypeId::of::<u32>());
+        let entry = ctn.entry::<u32>();
+        assert!(entry.shared_ctor.is_some());
+        assert!(entry.owned_ctor.is_some());
+
+        let mut ctn = ctn.build();
+        let shared: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***shared.inner(), 456);
+        assert_eq!(ctn.resolver().owned::<u32>(()).unwrap(), 456);
+    }
+
+    #[test]
+    fn inspect() {
+        let mut calls = 0;
+
+        let mut ctn = ContainerBuilder::new()
+            .with_owned_constructor::<u32>(|_, _| Ok(456))
+            .inspect(|builder| {
+                calls += 1;
+                assert!(format!("{:?}", builder).contains("u32"));
+            })
+            .with_owned_constructor::<()>(|_, _| Ok(()))
+            .build();
+
+        assert_eq!(calls, 1);
+        assert_eq!(ctn.resolver().owned::<u32>(()), Ok(456));
+        assert_eq!(ctn.resolver().owned::<()>(()), Ok(()));
+    }
+
+    #[test]
+    fn capturing_constructors() {
+        let base = String::from("postgres://localhost");
+        let shared_base = base.clone();
+
+        let mut ctn = ContainerBuilder::new()
+            .with_shared_constructor::<u32>(move |_| {
+                Ok(Rc::new(Access::new(shared_base.len() as u32)))
+            })
+            .with_owned_constructor::<u32>(move |_, _| Ok(base.len() as u32 * 2))
+            .build();
+
+        let shared: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***shared.inner(), 20);
+        assert_eq!(ctn.resolver().owned::<u32>(()).unwrap(), 40);
+    }
 
+    #[test]
+    fn with_shared_factory() {
+        let mut calls = 0;
+        let mut ctn = ContainerBuilder::new()
+            .with_shared_factory::<u32, _>(move |_| {
+                calls += 1;
+                Ok(Rc::new(Access::new(calls)))
+            })
+            .build();
+
+        let first: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***first.inner(), 1);
+        ctn.reset_shared::<u32>();
+        let second: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***second.inner(), 2);
+    }
+
+    #[test]
+    fn merge() {
+        let first = || {
+            ContainerBuilder::new()
+                .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(1))))
+                .with_owned_constructor::<u32>(|_, _| Ok(1))
+        };
+        let last = || {
+            ContainerBuilder::new()
+                .with_shared(Shared::<u32>::new(Rc::new(Access::new(2))))
+                .with_owned_constructor::<()>(|_, _| Ok(()))
+        };
+
+        let mut ctn = first().merge(last(), MergeStrategy::KeepFirst).build();
+        let shared: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***shared.inner(), 1);
+        assert_eq!(ctn.resolver().owned::<u32>(()).unwrap(), 1);
+        assert!(ctn.is_registered_owned::<()>());
+
+        let mut ctn = first().merge(last(), MergeStrategy::KeepLast).build();
+        let shared: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***shared.inner(), 2);
+        assert_eq!(ctn.resolver().owned::<u32>(()).unwrap(), 2468);
+    }
+
+    #[test]
+    #[should_panic(expected = "is registered in both containers")]
+    fn merge_panic() {
+        let builder = ContainerBuilder::new().with_owned_constructor::<u32>(|_, _| Ok(1));
+        let other = ContainerBuilder::new().with_owned_constructor::<u32>(|_, _| Ok(2));
+        builder.merge(other, MergeStrategy::Panic);
+    }
+
+    #[test]
+    fn try_build() {
+        struct Database;
+        impl IShared for Database {
+            type Pointer = Rc<Access<Database>>;
+            type Target = Database;
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Err(())
+            }
+        }
+        impl IOwned for Database {
+            type Instance = Database;
+            type Parameters = ();
+            type Error = ();
+            fn construct(_: Resolver, _: ()) -> Result<Self::Instance, Self::Error> {
+                Ok(Database)
+            }
+        }
+
+        struct Cache;
+        impl IShared for Cache {
+            type Pointer = Rc<Access<Cache>>;
+            type Target = Cache;
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new(Cache)))
+            }
+            fn dependencies() -> Vec<Dependency> {
+                vec![Dependency::registered::<Database>()]
+            }
+        }
+
+        struct Server;
+        impl IShared for Server {
+            type Pointer = Rc<Access<Server>>;
+            type Target = Server;
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new(Server)))
+            }
+            fn dependencies() -> Vec<Dependency> {
+                vec![Dependency::shared::<u32>(), Dependency::shared::<Cache>()]
+            }
+        }
+
+        let error = ContainerBuilder::new()
+            .with_eager_init::<Server>()
+            .try_build()
+            .unwrap_err();
         assert_eq!(
-            shared_ctor as *const (),
-            *entry.shared_ctor.as_ref().unwrap() as *const ()
+            error,
+            BuildError::MissingDependencies(vec![ValidationError {
+                type_id: TypeId::of::<Database>(),
+                type_name: Some(std::any::type_name::<Database>()),
+                required_by: std::any::type_name::<Cache>(),
+            }])
         );
 
+        let database = Shared::<Database>::new(Rc::new(Access::new(Database)));
+        let error = ContainerBuilder::new()
+            .with_shared_constructor::<Server>(|_| Ok(Rc::new(Access::new(Server))))
+            .with_named_shared("first", database.clone())
+            .try_build()
+            .unwrap_err();
         assert_eq!(
-            owned_ctor as *const (),
-            *entry.owned_ctor.as_ref().unwrap() as *const ()
+            error,
+            BuildError::MissingDependencies(vec![ValidationError {
+                type_id: TypeId::of::<Database>(),
+                type_name: Some(std::any::type_name::<Database>()),
+                required_by: std::any::type_name::<Cache>(),
+            }])
         );
+
+        let error = ContainerBuilder::new()
+            .with_eager_init::<Server>()
+            .with_owned_constructor::<Database>(|_, _| Ok(Database))
+            .try_build()
+            .unwrap_err();
+        assert_eq!(error.type_ids(), vec![TypeId::of::<Database>()]);
+
+        let mut ctn = ContainerBuilder::new()
+            .with_eager_init::<Server>()
+            .with_shared(database)
+            .try_build()
+            .unwrap();
+        assert!(ctn.contains_shared::<Server>());
+        let _: Shared<Cache> = ctn.resolver().shared().unwrap();
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+
+        assert!(ContainerBuilder::new()
+            .with_shared(Shared::<Server>::new(Rc::new(Access::new(Server))))
+            .try_build()
+            .is_ok());
+    }
+
+    #[test]
+    fn try_build_constructs_eagerly() {
+        struct Broken;
+        impl IShared for Broken {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Err(())
+            }
+        }
+
+        let mut ctn = ContainerBuilder::new()
+            .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(1))))
+            .with_eager_init::<()>()
+            .try_build()
+            .unwrap();
+        assert!(ctn.contains_shared::<u32>());
+        assert!(ctn.contains_shared::<()>());
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+
+        let error = ContainerBuilder::new()
+            .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(1))))
+            .with_eager_init::<Broken>()
+            .try_build()
+            .unwrap_err();
+        assert_eq!(error.type_ids(), vec![TypeId::of::<Broken>()]);
+    }
+
+    #[test]
+    fn with_modules() {
+        fn register_shared(builder: ContainerBuilder) -> ContainerBuilder {
+            builder.with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(10))))
+        }
+
+        struct OwnedModule(u32);
+        impl Module for OwnedModule {
+            fn register(self, builder: ContainerBuilder) -> ContainerBuilder {
+                builder.with_owned_constructor::<u32>(move |_, _| Ok(self.0))
+            }
+        }
+
+        let mut ctn = ContainerBuilder::new()
+            .with_modules(&[register_shared])
+            .with_module(OwnedModule(20))
+            .build();
+        let shared: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***shared.inner(), 10);
+        assert_eq!(ctn.resolver().owned::<u32>(()).unwrap(), 20);
+    }
+
+    #[test]
+    fn with_shared_constructor_checked() {
+        let mut ctn = ContainerBuilder::new()
+            .with_shared_constructor_checked::<u32, Rc<Access<u32>>>(|_| {
+                Ok(Rc::new(Access::new(7)))
+            })
+            .build();
+        let shared: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***shared.inner(), 7);
+        assert!(ctn.validate().is_ok());
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic(expected = "instead of"))]
+    fn with_shared_constructor_checked_mismatch() {
+        use std::sync::Arc;
+
+        let ctn = ContainerBuilder::new()
+            .with_shared_constructor_checked::<u32, Arc<Access<u32>>>(|_| {
+                Ok(Arc::new(Access::new(7)))
+            })
+            .build();
+        let issues = ctn.validate().unwrap_err();
+        assert_eq!(
+            issues[0].expected,
+            std::any::type_name::<Arc<Access<u32>>>()
+        );
+        assert_eq!(issues[0].found, std::any::type_name::<Rc<Access<u32>>>());
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    #[should_panic(expected = "instead of")]
+    fn with_shared_constructor_checked_mismatch_resolved() {
+        use std::sync::Arc;
+
+        let mut ctn = ContainerBuilder::new()
+            .with_shared_constructor_checked::<u32, Arc<Access<u32>>>(|_| {
+                Ok(Arc::new(Access::new(7)))
+            })
+            .build();
+        let _ = ctn.resolver().shared::<u32>();
     }
 }
@@ -0,0 +1,243 @@
+//! Registrations that are switched on and off by a configuration file.
+
+use crate::builder::ContainerBuilder;
+use std::fmt;
+use std::path::Path;
+
+/// The parameters of a provider, the keys of its table in the configuration
+/// file other than `enabled`.
+pub use toml::Table as ProviderParams;
+
+/// Adds the registrations of a provider to the builder.
+type Provider = Box<dyn Fn(ContainerBuilder, &ProviderParams) -> ContainerBuilder>;
+
+/// The providers that can be switched on and off by a configuration file,
+/// see [`ContainerBuilder::from_config()`].
+///
+/// A provider is a named group of registrations. The registrations are
+/// still written in code, the configuration only decides which providers
+/// are used and with which parameters.
+#[derive(Default)]
+pub struct ProviderRegistry {
+    /// The providers in the order they were added, with their names.
+    providers: Vec<(&'static str, Provider)>,
+}
+
+impl ProviderRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a provider that is enabled unless the configuration disables it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a provider with the same name was already added.
+    pub fn with_provider<F>(mut self, name: &'static str, provider: F) -> Self
+    where
+        F: Fn(ContainerBuilder, &ProviderParams) -> ContainerBuilder + 'static,
+    {
+        if self.providers.iter().any(|(n, _)| *n == name) {
+            panic!("Provider `{}` is already registered", name);
+        }
+        self.providers.push((name, Box::new(provider)));
+        self
+    }
+}
+
+impl fmt::Debug for ProviderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.providers.iter().map(|(name, _)| name).collect();
+        f.debug_struct("ProviderRegistry")
+            .field("providers", &names)
+            .finish()
+    }
+}
+
+/// An error while reading a configuration file, see
+/// [`ContainerBuilder::from_config()`].
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The file is not valid TOML.
+    Parse(toml::de::Error),
+    /// The configuration mentions a provider that is not in the registry.
+    UnknownProvider(String),
+    /// The configuration of a provider is not a table, or its `enabled` key
+    /// is not a boolean.
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read the configuration: {}", e),
+            Self::Parse(e) => write!(f, "failed to parse the configuration: {}", e),
+            Self::UnknownProvider(name) => write!(f, "unknown provider `{}`", name),
+            Self::Invalid(name) => write!(f, "invalid configuration of provider `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ContainerBuilder {
+    /// Creates a builder with the providers of `registry` that are enabled
+    /// in `config`, a TOML document.
+    ///
+    /// Every table in the document configures the provider with the same
+    /// name. `enabled = false` skips the provider, the other keys are passed
+    /// to the provider as its parameters. Providers that are not mentioned
+    /// are enabled with empty parameters.
+    ///
+    /// ```toml
+    /// [database]
+    /// url = "postgres://localhost"
+    ///
+    /// [metrics]
+    /// enabled = false
+    /// ```
+    pub fn from_config(registry: &ProviderRegistry, config: &str) -> Result<Self, ConfigError> {
+        let mut config: toml::Table = config.parse().map_err(ConfigError::Parse)?;
+        if let Some(name) = config
+            .keys()
+            .find(|name| !registry.providers.iter().any(|(n, _)| n == name))
+        {
+            return Err(ConfigError::UnknownProvider(name.clone()));
+        }
+
+        let mut builder = Self::new();
+        for (name, provider) in &registry.providers {
+            let mut params = match config.remove(*name) {
+                Some(toml::Value::Table(params)) => params,
+                Some(_) => return Err(ConfigError::Invalid(name.to_string())),
+                None => ProviderParams::new(),
+            };
+            let enabled = match params.remove("enabled") {
+                Some(toml::Value::Boolean(enabled)) => enabled,
+                Some(_) => return Err(ConfigError::Invalid(name.to_string())),
+                None => true,
+            };
+            if enabled {
+                builder = provider(builder, &params);
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Reads a configuration file and creates a builder from it, see
+    /// [`from_config()`](ContainerBuilder::from_config).
+    pub fn from_config_file<P: AsRef<Path>>(
+        registry: &ProviderRegistry,
+        path: P,
+    ) -> Result<Self, ConfigError> {
+        let config = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_config(registry, &config)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Access, IOwned, Resolver};
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Greeting;
+
+    impl IOwned for Greeting {
+        type Instance = String;
+        type Parameters = ();
+        type Error = ();
+
+        fn construct(_: Resolver, _: ()) -> Result<Self::Instance, Self::Error> {
+            Ok(String::from("hello"))
+        }
+    }
+
+    fn registry() -> ProviderRegistry {
+        ProviderRegistry::new()
+            .with_provider("greeting", |builder, params| {
+                let text = params
+                    .get("text")
+                    .and_then(|text| text.as_str())
+                    .unwrap_or("hi")
+                    .to_string();
+                builder.with_owned_constructor::<Greeting>(move |_, _| Ok(text.clone()))
+            })
+            .with_provider("number", |builder, _| {
+                builder.with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(10))))
+            })
+    }
+
+    #[test]
+    fn from_config() {
+        let config = r#"
+            [greeting]
+            text = "welcome"
+
+            [number]
+            enabled = false
+        "#;
+        let mut ctn = ContainerBuilder::from_config(&registry(), config)
+            .unwrap()
+            .build();
+        assert_eq!(ctn.resolver().owned::<Greeting>(()).unwrap(), "welcome");
+        let number = ctn.resolver().shared::<u32>().unwrap();
+        assert_eq!(***number.inner(), 1234);
+    }
+
+    #[test]
+    fn from_config_file() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let file = format!(
+            "rscontainer_from_config_file_{}_{}.toml",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(file);
+        std::fs::write(&path, "[number]\nenabled = false\n").unwrap();
+        let builder = ContainerBuilder::from_config_file(&registry(), &path);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut ctn = builder.unwrap().build();
+        assert_eq!(ctn.resolver().owned::<Greeting>(()).unwrap(), "hi");
+        let number = ctn.resolver().shared::<u32>().unwrap();
+        assert_eq!(***number.inner(), 1234);
+
+        let result = ContainerBuilder::from_config_file(&registry(), &path);
+        assert!(matches!(result, Err(ConfigError::Io(_))));
+    }
+
+    #[test]
+    fn from_config_defaults() {
+        let mut ctn = ContainerBuilder::from_config(&registry(), "")
+            .unwrap()
+            .build();
+        assert_eq!(ctn.resolver().owned::<Greeting>(()).unwrap(), "hi");
+        let number = ctn.resolver().shared::<u32>().unwrap();
+        assert_eq!(***number.inner(), 10);
+    }
+
+    #[test]
+    fn from_config_errors() {
+        let registry = registry();
+        let result = ContainerBuilder::from_config(&registry, "[logger]");
+        assert!(matches!(result, Err(ConfigError::UnknownProvider(name)) if name == "logger"));
+
+        let result = ContainerBuilder::from_config(&registry, "[number]\nenabled = 1");
+        assert!(matches!(result, Err(ConfigError::Invalid(name)) if name == "number"));
+
+        let result = ContainerBuilder::from_config(&registry, "greeting = 1");
+        assert!(matches!(result, Err(ConfigError::Invalid(_))));
+
+        let result = ContainerBuilder::from_config(&registry, "[greeting");
+        assert!(matches!(result, Err(ConfigError::Parse(_))));
+    }
+}
@@ -1,12 +1,22 @@
 //! Container version 2.0
 
-use crate::internal_helpers::{OwnedCtor, SharedCtor, SharedPtr, TypeErasedService};
+use crate::access::access_timeout;
+use crate::internal_helpers::TypeErasedService;
+use crate::internal_helpers::{named_key, service_key, teardown, ServiceKey, Teardown};
+use crate::internal_helpers::{OwnedCtor, OwnedPool, SharedCtor, SharedFallback, SharedPtr};
 use crate::pointers::ISharedPointer;
-use crate::service_traits::{IOwned, IShared};
-use crate::ContainerBuilder;
-use crate::Resolver;
+use crate::service_traits::{IAsyncShared, IOwned, IShared};
+use crate::ValidationIssue;
+use crate::{AsyncResolver, DisposeError, PreloadError, Resolver, ScopedContainer};
+use crate::{ContainerBuilder, MergeStrategy, Shared};
 use fnv::FnvHashMap;
 use std::any::TypeId;
+#[cfg(feature = "timing")]
+use std::collections::HashMap;
+use std::ptr::NonNull;
+use std::time::Duration;
+#[cfg(feature = "timing")]
+use std::time::Instant;
 
 ///////////////////////////////////////////////////////////////////////////////
 // Container
@@ -16,27 +26,67 @@ use std::any::TypeId;
 #[derive(Debug, Default)]
 pub struct ServiceContainer {
     /// The services in the container.
-    services: FnvHashMap<TypeId, TypeErasedService>,
+    services: FnvHashMap<ServiceKey, TypeErasedService>,
+    /// Whether inserting services from outside is disabled.
+    frozen: bool,
+    /// The container this container is a child of, see [`ScopedContainer`].
+    parent: Option<NonNull<ServiceContainer>>,
+    /// The keys and type names of the shared services that are currently
+    /// being constructed, to detect circular dependencies.
+    constructing: Vec<(ServiceKey, &'static str)>,
+    /// The type names of the services that are currently being constructed,
+    /// from the outermost to the innermost.
+    breadcrumbs: Vec<&'static str>,
+    /// The breadcrumbs at the moment the last construction failed.
+    failed_path: Option<Vec<&'static str>>,
+    /// The number of shared instances that were stored, to dispose them in
+    /// reverse order.
+    stored: u64,
+    /// How long access may wait for a lock in debug builds before it
+    /// panics, or `None` for the default.
+    access_timeout: Option<Duration>,
+    /// The time it took to construct each shared service.
+    #[cfg(feature = "timing")]
+    timings: FnvHashMap<TypeId, Duration>,
 }
 
 impl ServiceContainer {
     /// Creates a new service container.
     pub fn new() -> Self {
-        ServiceContainer {
-            services: FnvHashMap::default(),
-        }
+        Self::default()
     }
 
     /// Creates a new service container with a specified capacity.
     pub fn with_capacity(capacity: usize) -> Self {
-        ServiceContainer {
-            services: FnvHashMap::with_capacity_and_hasher(capacity, Default::default()),
-        }
+        Self::new_built(FnvHashMap::with_capacity_and_hasher(
+            capacity,
+            Default::default(),
+        ))
     }
 
     /// Creates a container that is already built by the ContainerBuilder.
-    pub(crate) fn new_built(services: FnvHashMap<TypeId, TypeErasedService>) -> Self {
-        Self { services }
+    pub(crate) fn new_built(services: FnvHashMap<ServiceKey, TypeErasedService>) -> Self {
+        // The fields can't be moved out of a default container, because it
+        // implements `Drop`.
+        let mut ctn = Self::default();
+        ctn.services = services;
+        ctn
+    }
+
+    /// Creates a container that is a child of another container.
+    ///
+    /// # Safety
+    ///
+    /// The parent must outlive the child and must not be mutated while the
+    /// child exists.
+    pub(crate) unsafe fn new_child(
+        parent: &ServiceContainer,
+        services: FnvHashMap<ServiceKey, TypeErasedService>,
+    ) -> Self {
+        let mut ctn = Self::new_built(services);
+        ctn.parent = Some(NonNull::from(parent));
+        ctn.access_timeout = parent.access_timeout;
+        ctn
     }
 
     /// Creates a ContainerBuilder.
@@ -52,7 +102,7 @@ impl ServiceContainer {
     /// Returns the inner hashmap for testing purposes.
     #[cfg(test)]
     #[allow(unused)]
-    fn inner(&self) -> &FnvHashMap<TypeId, TypeErasedService> {
+    fn inner(&self) -> &FnvHashMap<ServiceKey, TypeErasedService> {
         &self.services
     }
 
@@ -60,11 +110,513 @@ impl ServiceContainer {
     ///
     /// Panics if the instance already exists, because it is not allowed to
     /// mutate the container in such a way that other services will be
-    /// shadowed.
+    /// shadowed. Also panics if the container is [frozen].
+    ///
+    /// [frozen]: ServiceContainer::freeze
     pub fn insert<S: 'static + ?Sized + IShared>(&mut self, instance: S::Pointer) {
-        let entry = self.services.entry(TypeId::of::<S>()).or_default();
+        assert!(!self.frozen, "Cannot insert into a frozen container");
+        self.store::<S>(instance);
+    }
+
+    /// Inserts a named shared instance, next to the unnamed instance and the
+    /// instances with other names.
+    ///
+    /// Panics under the same conditions as [`insert`].
+    ///
+    /// [`insert`]: ServiceContainer::insert
+    pub fn insert_named<S: 'static + ?Sized + IShared>(
+        &mut self,
+        name: &str,
+        instance: S::Pointer,
+    ) {
+        assert!(!self.frozen, "Cannot insert into a frozen container");
+        self.store_shared_keyed::<S>(named_key::<S>(name), instance);
+    }
+
+    /// Moves the services of another container into this one, for example
+    /// one from a different subsystem.
+    ///
+    /// Only the services of `other` itself are merged, not those of its
+    /// parents. The merged instances count as stored after the instances of
+    /// this container, in the order in which they were stored in `other`.
+    /// An instance that is not kept is torn down, see
+    /// [`IShared::teardown()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the container is frozen, or if a service is stored or
+    /// registered in both containers and the strategy is
+    /// [`MergeStrategy::Panic`].
+    pub fn merge_from(&mut self, mut other: ServiceContainer, strategy: MergeStrategy) {
+        assert!(!self.frozen, "Cannot insert into a frozen container");
+        let mut services: Vec<_> = other.services.drain().collect();
+        services.sort_by_key(|(_, service)| service.stored);
+
+        for (key, mut service) in services {
+            if self.services.contains_key(&key) {
+                match strategy {
+                    MergeStrategy::KeepFirst => {
+                        // Torn down when `other` is dropped.
+                        other.services.insert(key, service);
+                        continue;
+                    }
+                    MergeStrategy::KeepLast => {
+                        self.tear_down(vec![key]);
+                    }
+                    MergeStrategy::Panic => {
+                        panic!(
+                            "Service `{}` is registered in both containers",
+                            service.name
+                        )
+                    }
+                }
+            }
+            if service.shared_ptr.is_some() {
+                self.stored += 1;
+                service.stored = self.stored;
+            }
+            self.services.insert(key, service);
+        }
+    }
+
+    /// Removes the shared instance from the container and returns it, so it
+    /// will be constructed again the next time it is resolved.
+    ///
+    /// Registered constructors are kept. Instances that were already
+    /// resolved stay alive until their last pointer is dropped. Returns
+    /// `None` if there is no instance stored.
+    pub fn remove_shared<S: 'static + ?Sized + IShared>(&mut self) -> Option<S::Pointer> {
+        let ptr = self
+            .services
+            .get_mut(&service_key::<S>())?
+            .shared_ptr
+            .take()?;
+        // SAFETY: because the TypeId is the key, we're certain that we're
+        // casting to the right type.
+        Some(unsafe { ptr.into_inner() })
+    }
+
+    /// Replaces the shared instance with another one, for example a mock in
+    /// a test, and returns the old instance.
+    ///
+    /// The old instance is torn down first, see [`IShared::teardown()`].
+    /// Services that already resolved the old instance keep using it. Only
+    /// available with the `test-util` feature, because this shadows the
+    /// instance that other services see.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the container is frozen.
+    #[cfg(feature = "test-util")]
+    pub fn replace_shared<S: 'static + ?Sized + IShared>(
+        &mut self,
+        instance: S::Pointer,
+    ) -> Option<S::Pointer> {
+        assert!(!self.frozen, "Cannot insert into a frozen container");
+        let old = self.tear_down(vec![service_key::<S>()]).pop();
+        self.store::<S>(instance);
+        // SAFETY: because the TypeId is the key, we're certain that we're
+        // casting to the right type.
+        old.map(|ptr| unsafe { ptr.into_inner() })
+    }
+
+    /// Tears down the shared instance and drops it, see [`remove_shared`].
+    /// Returns `true` if an instance was removed.
+    ///
+    /// [`remove_shared`]: ServiceContainer::remove_shared
+    pub fn reset_shared<S: 'static + ?Sized + IShared>(&mut self) -> bool {
+        !self.tear_down(vec![service_key::<S>()]).is_empty()
+    }
+
+    /// Gives an owned instance back to the pool of `S`, so it can be reused
+    /// by [`Resolver::owned_pooled()`].
+    ///
+    /// The instance is dropped if pooling is not enabled for `S`, see
+    /// [`ContainerBuilder::with_owned_pool()`].
+    pub fn release_owned<S: 'static + ?Sized + IOwned>(&mut self, instance: S::Instance) {
+        if let Some(pool) = self.owned_pool::<S>() {
+            pool.borrow_mut().push(instance);
+        }
+    }
+
+    /// Returns the pool of owned instances of `S`, if pooling is enabled.
+    fn owned_pool<S: 'static + ?Sized + IOwned>(&self) -> Option<OwnedPool<S>> {
+        let pool = self
+            .services
+            .get(&service_key::<S>())?
+            .owned_pool
+            .as_ref()?;
+        // SAFETY: because the TypeId is the key, we're certain that we're
+        // casting to the right type.
+        Some(unsafe { OwnedPool::<S>::clone_from_ptr(pool.ptr) })
+    }
+
+    /// Returns true if a shared instance of `S` is stored in the container
+    /// or in one of its parents.
+    ///
+    /// Never constructs the instance.
+    pub fn contains_shared<S: 'static + ?Sized + IShared>(&self) -> bool {
+        self.find_shared(service_key::<S>())
+            .is_some_and(|entry| entry.shared_ptr.is_some())
+    }
+
+    /// Returns true if a shared instance or a custom shared constructor of
+    /// `S` is registered in the container or in one of its parents.
+    pub fn is_registered_shared<S: 'static + ?Sized + IShared>(&self) -> bool {
+        self.find_shared(service_key::<S>()).is_some()
+    }
+
+    /// Returns true if a custom owned constructor of `S` is registered in the
+    /// container or in one of its parents.
+    pub fn is_registered_owned<S: 'static + ?Sized + IOwned>(&self) -> bool {
+        self.find_owned(service_key::<S>()).is_some()
+    }
+
+    /// Returns the number of services in this container, whether they are
+    /// constructed or only registered. Services of the parents are not
+    /// counted.
+    pub fn len(&self) -> usize {
+        self.services.len()
+    }
+
+    /// Returns true if there are no services in this container.
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty()
+    }
+
+    /// Returns the number of shared instances stored in this container.
+    pub fn count_constructed(&self) -> usize {
+        self.services
+            .values()
+            .filter(|service| service.shared_ptr.is_some())
+            .count()
+    }
+
+    /// Stores a shared instance, regardless of whether the container is
+    /// frozen.
+    pub(crate) fn store<S: 'static + ?Sized + IShared>(&mut self, instance: S::Pointer) {
+        self.store_shared_keyed::<S>(service_key::<S>(), instance);
+    }
+
+    /// Stores an asynchronous shared instance under the key of the service.
+    pub(crate) fn store_async<S: 'static + ?Sized + IAsyncShared>(&mut self, instance: S::Pointer) {
+        self.store_keyed::<S, _>(service_key::<S>(), instance);
+    }
+
+    /// Stores a smart pointer of the service `S` under the given key.
+    fn store_keyed<S: ?Sized, P: ISharedPointer + 'static>(
+        &mut self,
+        key: ServiceKey,
+        instance: P,
+    ) {
+        let entry = self
+            .services
+            .entry(key)
+            .or_insert_with(TypeErasedService::of::<S>);
         assert!(entry.shared_ptr.is_none());
-        entry.shared_ptr = Some(SharedPtr::new(instance));
+        entry.set_shared_ptr(instance);
+        self.stored += 1;
+        entry.stored = self.stored;
+    }
+
+    /// Stores a shared instance of `S` under the given key, which is torn
+    /// down when the container is dropped.
+    fn store_shared_keyed<S: 'static + ?Sized + IShared>(
+        &mut self,
+        key: ServiceKey,
+        instance: S::Pointer,
+    ) {
+        self.store_keyed::<S, _>(key, instance);
+        if let Some(entry) = self.services.get_mut(&key) {
+            entry.teardown = Some(teardown::<S>);
+        }
+    }
+
+    /// Takes the shared instances under `keys` out of the container, and
+    /// tears them down in the reverse order in which they were stored.
+    /// Returns the pointers in that order.
+    fn tear_down(&mut self, keys: Vec<ServiceKey>) -> Vec<SharedPtr> {
+        self.release(keys, |_, _| {})
+    }
+
+    /// Takes the shared instances under `keys` out of the container in the
+    /// reverse order in which they were stored, and calls `before` and then
+    /// the teardown on each of them. Returns the pointers in that order.
+    ///
+    /// Each pointer is taken out before its hooks run, so an instance is
+    /// released only once, even if a hook resolves the service again.
+    fn release(
+        &mut self,
+        keys: Vec<ServiceKey>,
+        mut before: impl FnMut(&TypeErasedService, &SharedPtr),
+    ) -> Vec<SharedPtr> {
+        let mut keys: Vec<_> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let service = self.services.get(&key)?;
+                service.shared_ptr.as_ref()?;
+                Some((service.stored, key))
+            })
+            .collect();
+        keys.sort_by_key(|&(stored, _)| std::cmp::Reverse(stored));
+
+        let mut pointers = Vec::with_capacity(keys.len());
+        for (_, key) in keys {
+            let service = match self.services.get_mut(&key) {
+                Some(service) => service,
+                None => continue,
+            };
+            let ptr = match service.shared_ptr.take() {
+                Some(ptr) => ptr,
+                None => continue,
+            };
+            before(service, &ptr);
+            if let Some(teardown) = service.teardown {
+                // SAFETY: the teardown is registered for the same service as
+                // the pointer, because the TypeId is the key.
+                unsafe { teardown(&ptr, self.resolver()) };
+            }
+            pointers.push(ptr);
+        }
+        pointers
+    }
+
+    /// Shuts down the shared instances of the services that are registered
+    /// with [`ContainerBuilder::with_dispose()`], in the reverse order of
+    /// construction.
+    ///
+    /// Instances that were given to the builder are disposed last. All
+    /// services are disposed, even if some fail. Each instance is taken out
+    /// of the container and torn down after it is disposed, so it is only
+    /// disposed once, and a new instance is constructed if the service is
+    /// resolved again.
+    pub fn dispose_all(&mut self) -> Result<(), Vec<DisposeError>> {
+        let keys = self
+            .services
+            .iter()
+            .filter(|(_, service)| service.disposer.is_some())
+            .map(|(key, _)| *key)
+            .collect();
+
+        let mut errors = Vec::new();
+        self.release(keys, |service, ptr| {
+            if let Some(disposer) = service.disposer {
+                // SAFETY: the disposer is registered for the same service as
+                // the pointer, because the TypeId is the key.
+                if let Err(error) = unsafe { disposer(ptr) } {
+                    errors.push(DisposeError {
+                        service: service.name,
+                        error,
+                    });
+                }
+            }
+        });
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Checks that every stored shared instance, and every custom shared
+    /// constructor, has the pointer type that was recorded when the service
+    /// was registered.
+    ///
+    /// The services are type erased, so a mismatch can't be caught by the
+    /// compiler and would be undefined behaviour when the service is
+    /// resolved. Call this in tests or at startup.
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+        let issues: Vec<_> = self
+            .services
+            .values()
+            .filter_map(|service| {
+                let found = match &service.shared_ptr {
+                    Some(ptr) => ptr.pointer,
+                    None => service.ctor_pointer?,
+                };
+                let expected = service.pointer?;
+                (found.id != expected.id).then_some(ValidationIssue {
+                    service: service.name,
+                    expected: expected.name,
+                    found: found.name,
+                })
+            })
+            .collect();
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Disables inserting services from outside the container.
+    ///
+    /// Services that are constructed during resolution are still stored as
+    /// usual. Use [`thaw`] to allow inserting services again, for example
+    /// during a controlled reconfiguration.
+    ///
+    /// [`thaw`]: ServiceContainer::thaw
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Allows inserting services again after the container was frozen.
+    pub fn thaw(&mut self) {
+        self.frozen = false;
+    }
+
+    /// Returns true if the container is frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Captures the shared instances that are currently in the container.
+    ///
+    /// The checkpoint holds its own reference to each instance, so they are
+    /// kept alive until the checkpoint is restored or dropped. See
+    /// [`restore`].
+    ///
+    /// [`restore`]: ServiceContainer::restore
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            instances: self
+                .services
+                .iter()
+                .filter_map(|(&key, entry)| {
+                    let instance = CheckpointInstance {
+                        ptr: entry.shared_ptr.clone()?,
+                        name: entry.name,
+                        teardown: entry.teardown,
+                        stored: entry.stored,
+                    };
+                    Some((key, instance))
+                })
+                .collect(),
+        }
+    }
+
+    /// Restores the shared instances to the state of a checkpoint.
+    ///
+    /// Instances that were added or replaced after the checkpoint are torn
+    /// down and removed from the container, and instances that were removed
+    /// are put back, with the name, teardown and construction order they had
+    /// at the checkpoint. Registered constructors are left untouched.
+    ///
+    /// Panics if the container is [frozen], because restoring may replace
+    /// existing instances.
+    ///
+    /// [frozen]: ServiceContainer::freeze
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        assert!(!self.frozen, "Cannot restore a frozen container");
+        let mut instances = checkpoint.instances;
+        let changed: Vec<_> = self
+            .services
+            .iter()
+            .filter_map(|(key, entry)| {
+                let current = entry.shared_ptr.as_ref()?;
+                match instances.get(key) {
+                    Some(instance) if instance.ptr.ptr == current.ptr => {
+                        instances.remove(key);
+                        None
+                    }
+                    _ => Some(*key),
+                }
+            })
+            .collect();
+        self.tear_down(changed);
+        for (key, instance) in instances {
+            let entry = self
+                .services
+                .entry(key)
+                .or_insert_with(|| TypeErasedService {
+                    name: instance.name,
+                    ..Default::default()
+                });
+            entry.shared_ptr = Some(instance.ptr);
+            entry.teardown = entry.teardown.or(instance.teardown);
+            entry.stored = instance.stored;
+        }
+    }
+
+    /// Returns the services that are in the given state, as pairs of their
+    /// `TypeId` and type name.
+    pub fn services_by_state(
+        &self,
+        state: ServiceState,
+    ) -> impl Iterator<Item = (TypeId, &'static str)> + '_ {
+        self.services
+            .iter()
+            .filter(move |(_, entry)| entry.state() == Some(state))
+            .map(|(&(id, _), entry)| (id, entry.name))
+    }
+
+    /// Returns the `TypeId`s of the services in this container. A service
+    /// that is stored under multiple names is returned once for each name.
+    pub fn registered_types(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.services.keys().map(|&(id, _)| id)
+    }
+
+    /// Describes every service in this container, for example to check the
+    /// registrations at startup.
+    pub fn describe(&self) -> Vec<ServiceInfo> {
+        self.services
+            .iter()
+            .map(|(&(type_id, _), entry)| ServiceInfo {
+                type_id,
+                name: entry.name,
+                has_instance: entry.shared_ptr.is_some(),
+                has_shared_constructor: entry.shared_ctor.is_some(),
+                has_shared_fallback: entry.shared_fallback.is_some(),
+                shared_retry: entry.shared_retry,
+                has_owned_constructor: entry.owned_ctor.is_some(),
+                has_owned_pool: entry.owned_pool.is_some(),
+            })
+            .collect()
+    }
+
+    /// Creates a child container that inherits the shared services of this
+    /// container.
+    ///
+    /// See [`ScopedContainer`] for more information.
+    pub fn child(&self) -> ScopedContainer<'_> {
+        ScopedContainer::new(self, FnvHashMap::default())
+    }
+
+    /// Returns the parent container, if this is a child container.
+    fn parent(&self) -> Option<&ServiceContainer> {
+        // SAFETY: the scoped container borrows the parent for as long as
+        // the child exists.
+        self.parent.map(|parent| unsafe { &*parent.as_ptr() })
+    }
+
+    /// Finds the closest entry of a service that has a shared instance or a
+    /// shared constructor, starting at this container and moving up through
+    /// its parents.
+    fn find_shared(&self, key: ServiceKey) -> Option<&TypeErasedService> {
+        self.find(key, |service| {
+            service.shared_ptr.is_some() || service.shared_ctor.is_some()
+        })
+    }
+
+    /// Finds the closest entry of a service that has an owned constructor,
+    /// starting at this container and moving up through its parents.
+    fn find_owned(&self, key: ServiceKey) -> Option<&TypeErasedService> {
+        self.find(key, |service| service.owned_ctor.is_some())
+    }
+
+    /// Finds the closest entry of a service for which `has` returns true,
+    /// starting at this container and moving up through its parents.
+    fn find(
+        &self,
+        key: ServiceKey,
+        has: fn(&TypeErasedService) -> bool,
+    ) -> Option<&TypeErasedService> {
+        match self.services.get(&key) {
+            Some(service) if has(service) => Some(service),
+            _ => self.parent()?.find(key, has),
+        }
     }
 
     /// Creates a resolver that can be used to resolve services.
@@ -73,16 +625,125 @@ impl ServiceContainer {
         Resolver::new(self)
     }
 
+    /// Creates a resolver that can be used to resolve services that are
+    /// constructed asynchronously.
+    #[inline]
+    pub fn async_resolver<'ctn>(&'ctn mut self) -> AsyncResolver<'ctn> {
+        AsyncResolver::new(self)
+    }
+
+    /// Resolves multiple owned instances, one for each set of parameters.
+    ///
+    /// This is the same as calling [`Resolver::owned()`] in a loop, but
+    /// borrows the container only once for the whole batch.
+    pub fn owned_batch<S, I>(&mut self, params_list: I) -> Vec<Result<S::Instance, S::Error>>
+    where
+        S: 'static + ?Sized + IOwned,
+        I: IntoIterator<Item = S::Parameters>,
+    {
+        params_list
+            .into_iter()
+            .map(|params| self.resolve_owned::<S>(params))
+            .collect()
+    }
+
+    /// Constructs the shared instances of all services with a custom shared
+    /// constructor in this container, and of the services that were
+    /// registered with [`ContainerBuilder::with_eager_init()`], to fail at
+    /// startup instead of at the first resolve.
+    ///
+    /// Services that are already stored are skipped. All services are tried,
+    /// even if some fail, and the instances that succeed are stored. The
+    /// errors are type erased, resolve a failed service again to get its
+    /// error.
+    pub fn preload(&mut self) -> Result<(), Vec<PreloadError>> {
+        let services: Vec<_> = self
+            .services
+            .iter()
+            .filter(|(_, service)| service.shared_ptr.is_none())
+            .filter_map(|(&key, service)| Some((key, service.preloader?)))
+            .collect();
+
+        let mut errors = Vec::new();
+        for (key, preloader) in services {
+            let stored = self
+                .services
+                .get(&key)
+                .is_some_and(|service| service.shared_ptr.is_some());
+            if !stored && !preloader(self, key) {
+                errors.push(PreloadError {
+                    type_id: key.0,
+                    path: self.take_failed_path().unwrap_or_default(),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Resolves a shared instance once and returns `n` handles to it, for
+    /// example one for each worker.
+    ///
+    /// [`IShared::resolved()`] is called once, not for every handle.
+    pub fn shared_clones<S: 'static + ?Sized + IShared>(
+        &mut self,
+        n: usize,
+    ) -> Result<Vec<Shared<S>>, S::Error> {
+        let instance = self.resolve_shared::<S>()?;
+        Ok((0..n).map(|_| self.to_shared(instance.clone())).collect())
+    }
+
     ///////////////////////////////////////////////////////////////////////////
     // Specialized Resolve Methods
     ///////////////////////////////////////////////////////////////////////////
 
+    /// Returns a clone of the stored shared instance, without constructing
+    /// it if there is none.
+    pub(crate) fn get_shared<S: 'static + ?Sized + IShared>(&self) -> Option<S::Pointer> {
+        let ptr = self.find_shared(service_key::<S>())?.shared_ptr.as_ref()?;
+        // SAFETY: because the TypeId is the key, we're certain that we're
+        // casting to the right type.
+        Some(unsafe { S::Pointer::clone_from_ptr(ptr.ptr) })
+    }
+
+    /// Returns a clone of the stored asynchronous shared instance, without
+    /// constructing it if there is none.
+    pub(crate) fn get_async_shared<S: 'static + ?Sized + IAsyncShared>(
+        &self,
+    ) -> Option<S::Pointer> {
+        let ptr = self.find_shared(service_key::<S>())?.shared_ptr.as_ref()?;
+        // SAFETY: because the TypeId is the key, we're certain that we're
+        // casting to the right type.
+        Some(unsafe { S::Pointer::clone_from_ptr(ptr.ptr) })
+    }
+
     /// Resolves a shared instance.
     pub(crate) fn resolve_shared<S: 'static + ?Sized + IShared>(
         &mut self,
     ) -> Result<S::Pointer, S::Error> {
-        let mut instance = match self.services.get(&TypeId::of::<S>()) {
-            // There's an instance in the container, so we clone the smart pointer.
+        self.resolve_shared_at::<S>(service_key::<S>())
+    }
+
+    /// Resolves a named shared instance.
+    pub(crate) fn resolve_named_shared<S: 'static + ?Sized + IShared>(
+        &mut self,
+        name: &str,
+    ) -> Result<S::Pointer, S::Error> {
+        self.resolve_shared_at::<S>(named_key::<S>(name))
+    }
+
+    /// Resolves the shared instance of `S` with the given key.
+    fn resolve_shared_at<S: 'static + ?Sized + IShared>(
+        &mut self,
+        key: ServiceKey,
+    ) -> Result<S::Pointer, S::Error> {
+        let mut instance = match self.find_shared(key) {
+            // There's an instance in the container or in one of its parents,
+            // so we clone the smart pointer.
             Some(TypeErasedService {
                 shared_ptr: Some(ptr),
                 ..
@@ -92,24 +753,27 @@ impl ServiceContainer {
                 S::Pointer::clone_from_ptr(ptr.ptr)
             },
 
-            // There's no instance, but there is a custom constructor.
-            Some(TypeErasedService {
-                shared_ctor: Some(ctor),
-                ..
-            }) => unsafe {
+            // There's no instance, so use the custom constructor if there is
+            // one and the default constructor otherwise. An inherited
+            // constructor still stores the instance in this container,
+            // because the parents are read-only. The fallback and the retries
+            // may be registered without a constructor, so they are looked up
+            // separately.
+            service => {
+                let ctor = service.and_then(|service| service.shared_ctor.as_ref());
+                let fallback = self
+                    .find(key, |service| service.shared_fallback.is_some())
+                    .and_then(|service| service.shared_fallback.as_ref());
+                let retry = self
+                    .find(key, |service| service.shared_retry.is_some())
+                    .and_then(|service| service.shared_retry);
                 // SAFETY: because the TypeId is the key, we're certain
                 // that we're casting to the right type.
-                let ctor: SharedCtor<S> = std::mem::transmute(*ctor);
-                let instance = ctor(self.resolver())?;
-                self.insert::<S>(instance.clone());
-                instance
-            },
-
-            // There's no instance and no custom constructor, so use the
-            // default constructor.
-            _ => {
-                let instance = S::construct(self.resolver())?;
-                self.insert::<S>(instance.clone());
+                let ctor = ctor.map(|ctor| unsafe { SharedCtor::<S>::clone_from_ptr(ctor.ptr) });
+                let fallback = fallback
+                    .map(|fallback| unsafe { SharedFallback::<S>::clone_from_ptr(fallback.ptr) });
+                let instance = self.construct_shared::<S>(key, ctor, fallback, retry)?;
+                self.store_shared_keyed::<S>(key, instance.clone());
                 instance
             }
         };
@@ -118,31 +782,332 @@ impl ServiceContainer {
         Ok(instance)
     }
 
+    /// Calls the custom or the default constructor of a shared instance, as
+    /// many times as `retry` allows, and the fallback if that fails.
+    ///
+    /// Panics if the instance is already being constructed, because then
+    /// there is a circular dependency that would otherwise overflow the
+    /// stack. [`Resolver::shared_checked()`] checks for this first.
+    fn construct_shared<S: 'static + ?Sized + IShared>(
+        &mut self,
+        key: ServiceKey,
+        ctor: Option<SharedCtor<S>>,
+        fallback: Option<SharedFallback<S>>,
+        retry: Option<(u32, Duration)>,
+    ) -> Result<S::Pointer, S::Error> {
+        if self.is_constructing(key) {
+            panic!(
+                "Circular dependency detected while constructing `{}`",
+                std::any::type_name::<S>()
+            );
+        }
+
+        self.constructing.push((key, std::any::type_name::<S>()));
+        let constructing = Constructing { ctn: self };
+        constructing.ctn.timed(TypeId::of::<S>(), |ctn| {
+            ctn.breadcrumb::<S, _, _>(|ctn| {
+                let (mut attempts, backoff) = retry.unwrap_or((1, Duration::ZERO));
+                let result = loop {
+                    let result = match &ctor {
+                        Some(ctor) => (**ctor)(ctn.resolver()),
+                        None => S::construct(ctn.resolver()),
+                    };
+                    attempts = attempts.saturating_sub(1);
+                    match result {
+                        Err(..) if attempts > 0 => std::thread::sleep(backoff),
+                        result => break result,
+                    }
+                };
+                match (result, fallback) {
+                    (Err(e), Some(fallback)) => (*fallback)(e, ctn.resolver()),
+                    (result, _) => result,
+                }
+            })
+        })
+    }
+
+    /// Calls a constructor with the service `S` on top of the breadcrumbs,
+    /// and records the breadcrumbs if the constructor fails.
+    ///
+    /// A failure of a dependency inside the constructor keeps the longer path
+    /// of that dependency. A failure that the constructor recovered from is
+    /// forgotten when the constructor succeeds.
+    fn breadcrumb<S: ?Sized, T, E>(
+        &mut self,
+        ctor: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        if self.breadcrumbs.is_empty() {
+            self.failed_path = None;
+        }
+
+        self.breadcrumbs.push(std::any::type_name::<S>());
+        let crumb = Breadcrumb { ctn: self };
+        let result = ctor(crumb.ctn);
+        let ctn = &mut *crumb.ctn;
+        let nested = match &ctn.failed_path {
+            Some(path) => path.starts_with(&ctn.breadcrumbs),
+            None => false,
+        };
+        match &result {
+            Err(..) if !nested => ctn.failed_path = Some(ctn.breadcrumbs.clone()),
+            Ok(..) if nested => ctn.failed_path = None,
+            _ => {}
+        }
+        result
+    }
+
+    /// Reserves capacity in the stacks that track the services that are
+    /// being constructed.
+    pub(crate) fn reserve_resolve_stacks(&mut self, capacity: usize) {
+        self.constructing.reserve(capacity);
+        self.breadcrumbs.reserve(capacity);
+    }
+
+    /// Sets how long access may wait for a lock in debug builds.
+    pub(crate) fn set_access_timeout(&mut self, timeout: Option<Duration>) {
+        self.access_timeout = timeout;
+    }
+
+    /// Returns how long access may wait for a lock, or `None` to wait as long
+    /// as it takes.
+    pub(crate) fn access_timeout(&self) -> Option<Duration> {
+        access_timeout(self.access_timeout)
+    }
+
+    /// Wraps a shared instance of this container in a [`Shared`] that uses
+    /// the access timeout of the container.
+    pub(crate) fn to_shared<S: ?Sized + IShared>(&self, instance: S::Pointer) -> Shared<S> {
+        Shared::<S>::new(instance).with_timeout(self.access_timeout())
+    }
+
+    /// Returns the type names of the services that are being constructed,
+    /// from the outermost to the innermost.
+    pub(crate) fn breadcrumbs(&self) -> &[&'static str] {
+        &self.breadcrumbs
+    }
+
+    /// Returns true if the shared service with the given key is being
+    /// constructed.
+    fn is_constructing(&self, key: ServiceKey) -> bool {
+        self.constructing.iter().any(|(k, _)| *k == key)
+    }
+
+    /// Returns the shared services in the circular dependency if the service
+    /// with the given key is already being constructed, starting and ending
+    /// with that service.
+    pub(crate) fn circular_chain(&self, key: ServiceKey) -> Option<Vec<(TypeId, &'static str)>> {
+        let start = self.constructing.iter().rposition(|(k, _)| *k == key)?;
+        let mut chain: Vec<_> = self.constructing[start..]
+            .iter()
+            .map(|((id, _), name)| (*id, *name))
+            .collect();
+        chain.push(chain[0]);
+        Some(chain)
+    }
+
+    /// Returns the path from the outermost service to the service whose
+    /// constructor failed during the last resolution.
+    pub(crate) fn take_failed_path(&mut self) -> Option<Vec<&'static str>> {
+        self.failed_path.take()
+    }
+
+    /// Calls the constructor of a service and records how long it took.
+    #[cfg(feature = "timing")]
+    fn timed<T>(&mut self, id: TypeId, ctor: impl FnOnce(&mut Self) -> T) -> T {
+        let start = Instant::now();
+        let result = ctor(self);
+        *self.timings.entry(id).or_default() += start.elapsed();
+        result
+    }
+
+    /// Calls the constructor of a service.
+    #[cfg(not(feature = "timing"))]
+    #[inline(always)]
+    fn timed<T>(&mut self, _id: TypeId, ctor: impl FnOnce(&mut Self) -> T) -> T {
+        ctor(self)
+    }
+
+    /// Returns how long the construction of each shared service took.
+    ///
+    /// Only calls to the constructor are measured, which includes the
+    /// construction of its dependencies. Resolving an existing instance is
+    /// not recorded. If a service is constructed more than once, the
+    /// durations are added together.
+    #[cfg(feature = "timing")]
+    pub fn construction_timings(&self) -> HashMap<TypeId, Duration> {
+        self.timings.iter().map(|(&k, &v)| (k, v)).collect()
+    }
+
+    /// Takes an owned instance from the pool, or resolves a new one if the
+    /// pool is empty or disabled.
+    pub(crate) fn resolve_owned_pooled<S: 'static + ?Sized + IOwned>(
+        &mut self,
+        params: S::Parameters,
+    ) -> Result<S::Instance, S::Error> {
+        let pooled = self
+            .owned_pool::<S>()
+            .and_then(|pool| pool.borrow_mut().pop());
+        match pooled {
+            Some(instance) => Ok(instance),
+            None => self.resolve_owned::<S>(params),
+        }
+    }
+
     /// Resolves an owned instance.
     pub(crate) fn resolve_owned<S: 'static + ?Sized + IOwned>(
         &mut self,
         params: S::Parameters,
     ) -> Result<S::Instance, S::Error> {
-        let mut owned = match self.services.get(&TypeId::of::<S>()) {
-            // There is a custom constructor registered.
-            Some(TypeErasedService {
-                owned_ctor: Some(ctor),
-                ..
-            }) => unsafe {
-                // SAFETY: because the TypeId is the key, we're certain
-                // that we're casting to the right type.
-                let ctor: OwnedCtor<S> = std::mem::transmute(*ctor);
-                ctor(self.resolver(), params)?
-            },
+        // Use the custom constructor if one is registered here or in a
+        // parent, otherwise use the default one.
+        let ctor = self
+            .find_owned(service_key::<S>())
+            .and_then(|service| service.owned_ctor.as_ref())
+            // SAFETY: because the TypeId is the key, we're certain that
+            // we're casting to the right type.
+            .map(|ctor| unsafe { OwnedCtor::<S>::clone_from_ptr(ctor.ptr) });
+        let mut owned = self.breadcrumb::<S, _, _>(|ctn| match ctor {
+            Some(ctor) => (*ctor)(ctn.resolver(), params),
+            None => S::construct(ctn.resolver(), params),
+        })?;
+        S::resolved(&mut owned, self.resolver());
+        Ok(owned)
+    }
 
-            // There is no custom constructor, so use the default one.
-            _ => S::construct(self.resolver(), params)?,
-        };
+    /// Resolves an owned instance from borrowed parameters. A custom
+    /// constructor gets a clone of the parameters.
+    pub(crate) fn resolve_owned_ref<S: 'static + ?Sized + IOwned>(
+        &mut self,
+        params: &S::Parameters,
+    ) -> Result<S::Instance, S::Error>
+    where
+        S::Parameters: Clone,
+    {
+        let ctor = self
+            .find_owned(service_key::<S>())
+            .and_then(|service| service.owned_ctor.as_ref())
+            // SAFETY: because the TypeId is the key, we're certain that
+            // we're casting to the right type.
+            .map(|ctor| unsafe { OwnedCtor::<S>::clone_from_ptr(ctor.ptr) });
+        let mut owned = self.breadcrumb::<S, _, _>(|ctn| match ctor {
+            Some(ctor) => (*ctor)(ctn.resolver(), params.clone()),
+            None => S::construct_ref(ctn.resolver(), params),
+        })?;
         S::resolved(&mut owned, self.resolver());
         Ok(owned)
     }
 }
 
+impl Drop for ServiceContainer {
+    /// Tears down the stored shared instances, in the reverse order of
+    /// construction, before they are released.
+    fn drop(&mut self) {
+        let keys = self.services.keys().copied().collect();
+        self.tear_down(keys);
+    }
+}
+
+/// Resolves the shared instance of `S` under the given key, see
+/// [`ServiceContainer::preload()`].
+pub(crate) fn preload<S: 'static + ?Sized + IShared>(
+    ctn: &mut ServiceContainer,
+    key: ServiceKey,
+) -> bool {
+    ctn.resolve_shared_at::<S>(key).is_ok()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Service State
+///////////////////////////////////////////////////////////////////////////////
+
+/// The state of a service in the container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServiceState {
+    /// A custom shared constructor is registered and the shared instance is
+    /// stored.
+    Constructed,
+    /// A custom shared constructor is registered, but no shared instance is
+    /// stored yet.
+    ConstructorOnly,
+    /// A shared instance is stored without a custom shared constructor,
+    /// because it was inserted or constructed with the default constructor.
+    InstanceOnly,
+    /// Only a custom owned constructor is registered. Owned instances are
+    /// never stored, so the service is never constructed in this sense.
+    OwnedOnly,
+}
+
+/// A description of a service in the container, see
+/// [`ServiceContainer::describe()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInfo {
+    /// The `TypeId` of the service.
+    pub type_id: TypeId,
+    /// The type name of the service.
+    pub name: &'static str,
+    /// Whether a shared instance is stored.
+    pub has_instance: bool,
+    /// Whether a custom shared constructor is registered.
+    pub has_shared_constructor: bool,
+    /// Whether a fallback for the shared constructor is registered, see
+    /// [`ContainerBuilder::with_shared_fallback()`].
+    pub has_shared_fallback: bool,
+    /// The number of attempts and the backoff of the shared constructor, see
+    /// [`ContainerBuilder::with_shared_retry()`].
+    pub shared_retry: Option<(u32, Duration)>,
+    /// Whether a custom owned constructor is registered.
+    pub has_owned_constructor: bool,
+    /// Whether owned instances are pooled, see
+    /// [`ContainerBuilder::with_owned_pool()`].
+    pub has_owned_pool: bool,
+}
+
+/// Pops the top of the breadcrumbs of the container when it's dropped, also
+/// when a constructor panics.
+struct Breadcrumb<'a> {
+    ctn: &'a mut ServiceContainer,
+}
+
+impl Drop for Breadcrumb<'_> {
+    fn drop(&mut self) {
+        self.ctn.breadcrumbs.pop();
+    }
+}
+
+/// Pops the top of the services that are being constructed when it's
+/// dropped, also when a constructor panics.
+struct Constructing<'a> {
+    ctn: &'a mut ServiceContainer,
+}
+
+impl Drop for Constructing<'_> {
+    fn drop(&mut self) {
+        self.ctn.constructing.pop();
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Checkpoint
+///////////////////////////////////////////////////////////////////////////////
+
+/// The shared instances of a container at some point in time.
+///
+/// Created with [`ServiceContainer::checkpoint()`].
+#[derive(Debug)]
+pub struct Checkpoint {
+    /// Clones of the stored smart pointers.
+    instances: FnvHashMap<ServiceKey, CheckpointInstance>,
+}
+
+/// A shared instance in a checkpoint, with what's needed to store it again.
+#[derive(Debug)]
+struct CheckpointInstance {
+    ptr: SharedPtr,
+    name: &'static str,
+    teardown: Option<Teardown>,
+    stored: u64,
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // Tests
 ///////////////////////////////////////////////////////////////////////////////
@@ -150,9 +1115,14 @@ impl ServiceContainer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::internals::IAccessMut;
     use crate::Access;
-    use crate::Shared;
+    use crate::ResolveError;
+    use std::cell::{Cell, RefCell};
+    use std::panic::{self, AssertUnwindSafe};
     use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, LazyLock, Mutex, OnceLock, Weak};
 
     impl IShared for u32 {
         type Pointer = Rc<Access<u32>>;
@@ -361,4 +1331,1279 @@ mod tests {
         let result = ctn.resolver().owned::<u32>(());
         assert!(matches!(result, Err(())));
     }
+
+    #[test]
+    fn owned_batch() {
+        let mut ctn = ServiceContainer::builder()
+            .with_owned_constructor::<u32>(|_, _| Ok(1357))
+            .build();
+
+        let instances = ctn.owned_batch::<u32, _>(vec![(); 5]);
+        assert_eq!(instances.len(), 5);
+        assert!(instances.iter().all(|instance| *instance == Ok(1357)));
+    }
+
+    #[test]
+    fn preload() {
+        let constructed = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&constructed);
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(move |_| {
+                counter.set(counter.get() + 1);
+                Ok(Rc::new(Access::new(1)))
+            })
+            .with_shared_constructor::<Failing>(|_| Err("failed"))
+            .with_shared(Shared::<()>::new(Rc::new(Access::new(()))))
+            .build();
+
+        let errors = ctn.preload().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].type_id, TypeId::of::<Failing>());
+        assert_eq!(errors[0].path, vec![std::any::type_name::<Failing>()]);
+        assert!(ctn.contains_shared::<u32>());
+        assert_eq!(constructed.get(), 1);
+
+        let _ = ctn.preload();
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(constructed.get(), 1);
+    }
+
+    #[test]
+    fn shared_clones() {
+        let constructed = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&constructed);
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(move |_| {
+                counter.set(counter.get() + 1);
+                Ok(Rc::new(Access::new(1)))
+            })
+            .build();
+
+        let handles = ctn.shared_clones::<u32>(4).unwrap();
+        assert_eq!(handles.len(), 4);
+        assert!(handles.iter().all(|handle| handle.is(&handles[0])));
+        assert_eq!(constructed.get(), 1);
+        assert!(ctn.shared_clones::<Failing>(2).is_err());
+    }
+
+    #[test]
+    fn resolved_wires_back_reference() {
+        struct Node {
+            this: Weak<Mutex<Node>>,
+        }
+
+        impl IShared for Node {
+            type Pointer = Arc<Mutex<Node>>;
+            type Target = Node;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Arc::new(Mutex::new(Node { this: Weak::new() })))
+            }
+
+            fn resolved(this: &mut Self::Pointer, _: Resolver) {
+                let weak = Arc::downgrade(this);
+                this.access_mut(|node| node.assert_healthy().this = weak);
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let node: Shared<Node> = ctn.resolver().shared().unwrap();
+        let this = node.access(|node| node.assert_healthy().this.upgrade());
+        assert!(Arc::ptr_eq(&this.unwrap(), node.inner()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_frozen() {
+        let mut ctn = ServiceContainer::new();
+        ctn.freeze();
+        ctn.insert::<()>(Rc::new(Access::new(())));
+    }
+
+    #[test]
+    fn insert_thawed() {
+        let mut ctn = ServiceContainer::new();
+        ctn.freeze();
+        assert!(ctn.is_frozen());
+        ctn.thaw();
+        assert!(!ctn.is_frozen());
+        ctn.insert::<()>(Rc::new(Access::new(())));
+        assert_eq!(ctn.inner().len(), 1);
+    }
+
+    #[test]
+    fn resolve_shared_frozen() {
+        let mut ctn = ServiceContainer::new();
+        ctn.freeze();
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(ctn.inner().len(), 1);
+    }
+
+    #[test]
+    fn resolve_owned_with() {
+        let mut ctn = ServiceContainer::new();
+        let mut calls = 0;
+        let instance = ctn.resolver().owned_with::<u32, _>(|| calls += 1);
+        assert_eq!(instance, Ok(2468));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn restore_removes_later_instances() {
+        let mut ctn = ServiceContainer::new();
+        let unit = Rc::new(Access::new(()));
+        ctn.insert::<()>(Rc::clone(&unit));
+
+        let checkpoint = ctn.checkpoint();
+        assert_eq!(Rc::strong_count(&unit), 3);
+
+        let number: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(Rc::strong_count(number.inner()), 2);
+
+        ctn.restore(checkpoint);
+        assert_eq!(Rc::strong_count(number.inner()), 1);
+        assert_eq!(Rc::strong_count(&unit), 2);
+
+        let number_2: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert!(!number.is(&number_2));
+        let unit_2: Shared<()> = ctn.resolver().shared().unwrap();
+        assert!(Rc::ptr_eq(&unit, unit_2.inner()));
+    }
+
+    #[test]
+    fn restore_keeps_entry() {
+        let mut ctn = ServiceContainer::new();
+        let number: Shared<u32> = ctn.resolver().shared().unwrap();
+        let checkpoint = ctn.checkpoint();
+
+        let mut other = ServiceContainer::new();
+        other.restore(checkpoint);
+        let info = other.describe();
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0].name, "u32");
+        assert!(info[0].has_instance);
+        assert!(number.is(&other.resolver().shared().unwrap()));
+    }
+
+    #[test]
+    fn resolve_with_shared() {
+        struct Counter;
+
+        impl IShared for Counter {
+            type Pointer = Rc<RefCell<u32>>;
+            type Target = u32;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(RefCell::new(10)))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let mut resolver = ctn.resolver();
+        let new = resolver.with_shared_mut::<Counter, _, _>(|counter| {
+            *counter += 5;
+            *counter
+        });
+        assert_eq!(new, Ok(15));
+
+        let value = resolver.with_shared::<Counter, _, _>(|counter| *counter);
+        assert_eq!(value, Ok(15));
+    }
+
+    #[test]
+    fn services_by_state() {
+        struct Report;
+        impl IOwned for Report {
+            type Instance = Report;
+            type Parameters = ();
+            type Error = ();
+
+            fn construct(_: Resolver, _: ()) -> Result<Report, ()> {
+                Ok(Report)
+            }
+        }
+
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(5678))))
+            .with_shared_constructor::<Failing>(|_| Ok(Rc::new(Access::new(Failing))))
+            .with_owned_constructor::<()>(|_, _| Ok(()))
+            .with_owned_constructor::<Report>(|_, _| Ok(Report))
+            .build();
+        ctn.insert::<()>(Rc::new(Access::new(())));
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+
+        let constructed: Vec<_> = ctn.services_by_state(ServiceState::Constructed).collect();
+        assert_eq!(constructed, vec![(TypeId::of::<u32>(), "u32")]);
+
+        let ctor_only: Vec<_> = ctn
+            .services_by_state(ServiceState::ConstructorOnly)
+            .collect();
+        assert_eq!(ctor_only.len(), 1);
+        assert_eq!(ctor_only[0].0, TypeId::of::<Failing>());
+        assert!(ctor_only[0].1.ends_with("Failing"));
+
+        // The owned constructor of `()` doesn't construct its shared instance.
+        let instance_only: Vec<_> = ctn.services_by_state(ServiceState::InstanceOnly).collect();
+        assert_eq!(instance_only, vec![(TypeId::of::<()>(), "()")]);
+
+        let owned_only: Vec<_> = ctn.services_by_state(ServiceState::OwnedOnly).collect();
+        assert_eq!(owned_only.len(), 1);
+        assert_eq!(owned_only[0].0, TypeId::of::<Report>());
+    }
+
+    #[test]
+    fn describe() {
+        let ctn = ServiceContainer::builder()
+            .with_owned_constructor::<Failing>(|_, _| Ok(Failing))
+            .with_owned_pool::<Failing>()
+            .with_shared(Shared::<u32>::new(Rc::new(Access::new(1))))
+            .with_shared_fallback::<u32>(|e, _| Err(e))
+            .with_shared_retry::<u32>(3, Duration::from_millis(5))
+            .build();
+
+        let mut types: Vec<_> = ctn.registered_types().collect();
+        types.sort();
+        let mut expected = vec![TypeId::of::<u32>(), TypeId::of::<Failing>()];
+        expected.sort();
+        assert_eq!(types, expected);
+
+        let mut services = ctn.describe();
+        services.sort_by_key(|service| service.name);
+        assert_eq!(
+            services[1],
+            ServiceInfo {
+                type_id: TypeId::of::<u32>(),
+                name: "u32",
+                has_instance: true,
+                has_shared_constructor: false,
+                has_shared_fallback: true,
+                shared_retry: Some((3, Duration::from_millis(5))),
+                has_owned_constructor: false,
+                has_owned_pool: false,
+            }
+        );
+        assert!(services[0].has_owned_constructor);
+        assert!(services[0].has_owned_pool);
+        assert!(!services[0].has_instance);
+    }
+
+    #[test]
+    fn try_shared() {
+        let mut ctn = ServiceContainer::new();
+        assert!(ctn.resolver().try_shared::<u32>().is_none());
+        assert_eq!(ctn.inner().len(), 0);
+
+        let instance: Shared<u32> = ctn.resolver().shared().unwrap();
+        let existing = ctn.resolver().try_shared::<u32>().unwrap();
+        assert!(instance.is(&existing));
+    }
+
+    #[cfg(feature = "timing")]
+    #[test]
+    fn construction_timings() {
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|_| {
+                std::thread::sleep(Duration::from_millis(5));
+                Ok(Rc::new(Access::new(5678)))
+            })
+            .build();
+
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+        let timings = ctn.construction_timings();
+        assert_eq!(timings.len(), 1);
+        assert!(timings[&TypeId::of::<u32>()] >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn named_shared() {
+        let mut ctn = ServiceContainer::builder()
+            .with_named_shared_constructor::<u32>("primary", |_| Ok(Rc::new(Access::new(1))))
+            .build();
+        ctn.insert_named::<u32>("replica", Rc::new(Access::new(2)));
+
+        let primary: Shared<u32> = ctn.resolver().named_shared("primary").unwrap();
+        let replica: Shared<u32> = ctn.resolver().named_shared("replica").unwrap();
+        let unnamed: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***primary.inner(), 1);
+        assert_eq!(***replica.inner(), 2);
+        assert_eq!(***unnamed.inner(), 1234);
+
+        let primary_2: Shared<u32> = ctn.resolver().named_shared("primary").unwrap();
+        assert!(primary.is(&primary_2));
+        assert!(!primary.is(&replica));
+    }
+
+    #[test]
+    fn remove_shared() {
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(5678))))
+            .build();
+        assert!(ctn.remove_shared::<u32>().is_none());
+
+        let instance: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(Rc::strong_count(instance.inner()), 2);
+        let removed = ctn.remove_shared::<u32>().unwrap();
+        assert!(Rc::ptr_eq(&removed, instance.inner()));
+        assert_eq!(Rc::strong_count(instance.inner()), 2);
+        drop(removed);
+        assert_eq!(Rc::strong_count(instance.inner()), 1);
+        assert_eq!(***instance.inner(), 5678);
+
+        let instance_2: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert!(!instance.is(&instance_2));
+        assert_eq!(***instance_2.inner(), 5678);
+    }
+
+    #[test]
+    #[cfg(feature = "test-util")]
+    fn replace_shared() {
+        let mut ctn = ServiceContainer::new();
+        assert!(ctn.replace_shared::<u32>(Rc::new(Access::new(1))).is_none());
+
+        let old = ctn.replace_shared::<u32>(Rc::new(Access::new(2))).unwrap();
+        assert_eq!(**old, 1);
+        let instance: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***instance.inner(), 2);
+    }
+
+    #[test]
+    fn reset_shared() {
+        let mut ctn = ServiceContainer::new();
+        assert!(!ctn.reset_shared::<u32>());
+
+        let instance: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert!(ctn.reset_shared::<u32>());
+        assert_eq!(Rc::strong_count(instance.inner()), 1);
+        assert!(!ctn.contains_shared::<u32>());
+    }
+
+    struct Chicken;
+    struct Egg;
+
+    impl IShared for Chicken {
+        type Pointer = Rc<Access<()>>;
+        type Target = ();
+        type Error = ();
+
+        fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+            ctn.shared::<Egg>()?;
+            Ok(Rc::new(Access::new(())))
+        }
+    }
+
+    impl IShared for Egg {
+        type Pointer = Rc<Access<()>>;
+        type Target = ();
+        type Error = ();
+
+        fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+            ctn.shared::<Chicken>()?;
+            Ok(Rc::new(Access::new(())))
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Circular dependency detected while constructing")]
+    fn circular_dependency() {
+        let mut ctn = ServiceContainer::new();
+        let _ = ctn.resolver().shared::<Chicken>();
+    }
+
+    #[test]
+    fn circular_dependency_checked() {
+        struct Hen;
+        struct Nest;
+
+        impl IShared for Hen {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = String;
+
+            fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+                ctn.shared_checked::<Nest>().map_err(|e| e.to_string())?;
+                Ok(Rc::new(Access::new(())))
+            }
+        }
+
+        impl IShared for Nest {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = String;
+
+            fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+                ctn.shared_checked::<Hen>().map_err(|e| e.to_string())?;
+                Ok(Rc::new(Access::new(())))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let error = ctn.resolver().shared_checked::<Hen>().unwrap_err();
+        let hen = (TypeId::of::<Hen>(), std::any::type_name::<Hen>());
+        let nest = (TypeId::of::<Nest>(), std::any::type_name::<Nest>());
+        let cycle = ResolveError::<String>::CyclicDependency {
+            chain: vec![hen, nest, hen],
+        };
+        let error = error.into_service_error().unwrap();
+        assert!(error.ends_with(&cycle.to_string()));
+        assert!(ctn.constructing.is_empty());
+        assert!(ctn.breadcrumbs.is_empty());
+
+        let _: Shared<u32> = ctn.resolver().shared_checked().unwrap();
+    }
+
+    #[test]
+    fn named_dependency_is_not_circular() {
+        let mut ctn = ServiceContainer::builder()
+            .with_named_shared_constructor::<u32>("copy", |mut ctn| {
+                let original: Shared<u32> = ctn.shared_checked().map_err(|_| ())?;
+                Ok(Rc::new(Access::new(***original.inner())))
+            })
+            .build();
+        let copy: Shared<u32> = ctn.resolver().named_shared("copy").unwrap();
+        assert_eq!(***copy.inner(), 1234);
+    }
+
+    #[test]
+    fn circular_chain_by_key() {
+        let mut ctn = ServiceContainer::new();
+        let name = std::any::type_name::<u32>();
+        ctn.constructing.push((named_key::<u32>("copy"), name));
+        ctn.constructing.push((service_key::<Egg>(), "Egg"));
+        assert_eq!(ctn.circular_chain(service_key::<u32>()), None);
+
+        let chain = ctn.circular_chain(named_key::<u32>("copy")).unwrap();
+        let ids: Vec<_> = chain.iter().map(|(id, _)| *id).collect();
+        let u32_id = TypeId::of::<u32>();
+        assert_eq!(ids, vec![u32_id, TypeId::of::<Egg>(), u32_id]);
+    }
+
+    #[test]
+    fn failing_constructor_is_not_circular() {
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|mut ctn| {
+                ctn.shared::<Failing>().map_err(|_| ())?;
+                unreachable!()
+            })
+            .build();
+
+        assert!(ctn.resolver().shared::<u32>().is_err());
+        assert!(ctn.resolver().shared::<u32>().is_err());
+        assert!(ctn.constructing.is_empty());
+    }
+
+    #[test]
+    fn contains_and_is_registered() {
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(5678))))
+            .with_owned_constructor::<u32>(|_, _| Ok(1357))
+            .build();
+        assert!(!ctn.contains_shared::<u32>());
+        assert!(ctn.is_registered_shared::<u32>());
+        assert!(ctn.is_registered_owned::<u32>());
+        assert!(!ctn.is_registered_shared::<()>());
+        assert!(!ctn.is_registered_owned::<Failing>());
+
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert!(ctn.contains_shared::<u32>());
+    }
+
+    #[test]
+    fn len() {
+        let mut ctn = ServiceContainer::builder()
+            .with_owned_constructor::<u32>(|_, _| Ok(1357))
+            .build();
+        assert_eq!(ctn.len(), 1);
+        assert_eq!(ctn.count_constructed(), 0);
+        assert!(ServiceContainer::new().is_empty());
+
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+        let _: Shared<()> = ctn.resolver().shared().unwrap();
+        assert_eq!(ctn.len(), 2);
+        assert_eq!(ctn.count_constructed(), 2);
+        assert!(!ctn.is_empty());
+    }
+
+    #[test]
+    fn resolve_builder() {
+        #[derive(Default)]
+        struct Server {
+            config: Option<Shared<u32>>,
+            cache: Option<Shared<()>>,
+            port: Option<u32>,
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let server: Server = ctn
+            .resolver()
+            .resolve_builder::<_, ()>(Server::default())
+            .shared_field(|server, config| server.config = Some(config))
+            .shared_field(|server, cache| server.cache = Some(cache))
+            .finish()
+            .unwrap();
+        assert_eq!(***server.config.unwrap().inner(), 1234);
+        assert!(server.cache.is_some());
+        assert!(server.port.is_none());
+
+        let mut constructed = false;
+        let result = ctn
+            .resolver()
+            .resolve_builder::<_, &str>(Server::default())
+            .owned_field::<Failing, _>((), |_, _| ())
+            .shared_field::<Failing, _>(|_, _| constructed = true)
+            .finish();
+        assert_eq!(result.err(), Some("error456"));
+        assert!(!constructed);
+    }
+
+    #[test]
+    fn validate() {
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(5))))
+            .build();
+        let _: Shared<()> = ctn.resolver().shared().unwrap();
+        assert_eq!(ctn.validate(), Ok(()));
+
+        // The registration recorded `Rc<Access<u32>>`, store another pointer
+        // through the type erased storage.
+        ctn.store_keyed::<u32, _>(service_key::<u32>(), Rc::new(5u64));
+        assert_eq!(
+            ctn.validate(),
+            Err(vec![ValidationIssue {
+                service: std::any::type_name::<u32>(),
+                expected: std::any::type_name::<Rc<Access<u32>>>(),
+                found: std::any::type_name::<Rc<u64>>(),
+            }])
+        );
+    }
+
+    #[test]
+    fn access_both() {
+        #[derive(Debug, PartialEq)]
+        enum BothError {
+            Number,
+            Failing(&'static str),
+        }
+
+        impl From<()> for BothError {
+            fn from(_: ()) -> Self {
+                BothError::Number
+            }
+        }
+
+        impl From<&'static str> for BothError {
+            fn from(e: &'static str) -> Self {
+                BothError::Failing(e)
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let error = ctn
+            .resolver()
+            .access_both::<u32, Failing, BothError, _, _>(|n, _| *n.assert_healthy())
+            .unwrap_err();
+        assert_eq!(error, BothError::Failing("error123"));
+
+        let both = ctn
+            .resolver()
+            .access_both::<u32, (), (), _, _>(|n, unit| {
+                (*n.assert_healthy(), *unit.assert_healthy())
+            })
+            .unwrap();
+        assert_eq!(both, (1234, ()));
+    }
+
+    #[test]
+    #[should_panic(expected = "twice at once")]
+    fn access_both_same_service() {
+        let mut ctn = ServiceContainer::new();
+        let _ = ctn.resolver().access_both::<u32, u32, (), _, _>(|_, _| ());
+    }
+
+    #[test]
+    fn default_shared() {
+        struct Counter(u32);
+
+        impl IShared for Counter {
+            type Pointer = crate::DefaultShared<Counter>;
+            type Target = Counter;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(crate::DefaultShared::new(Counter(0).into()))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let counter: Shared<Counter> = ctn.resolver().shared().unwrap();
+        counter.access_mut(|counter| counter.assert_healthy().0 += 1);
+        let again: Shared<Counter> = ctn.resolver().shared().unwrap();
+        assert_eq!(again.access(|counter| counter.assert_healthy().0), 1);
+    }
+
+    #[test]
+    fn merge_from() {
+        let mut ctn = ServiceContainer::new();
+        let first: Shared<u32> = ctn.resolver().shared().unwrap();
+
+        let mut other = ServiceContainer::new();
+        other.insert::<u32>(Rc::new(Access::new(5678)));
+        let unit: Shared<()> = other.resolver().shared().unwrap();
+        ctn.merge_from(other, MergeStrategy::KeepLast);
+
+        let merged: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***merged.inner(), 5678);
+        assert_eq!(Rc::strong_count(first.inner()), 1);
+        assert!(unit.is(&ctn.resolver().shared().unwrap()));
+        assert_eq!(Rc::strong_count(unit.inner()), 2);
+    }
+
+    #[test]
+    fn dispose_all() {
+        use crate::IDispose;
+
+        thread_local! {
+            static DISPOSED: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+        }
+
+        struct Pool;
+        struct Cache;
+
+        impl IShared for Pool {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = &'static str;
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new(())))
+            }
+        }
+
+        impl IDispose for Pool {
+            fn dispose(_: &Self::Pointer) -> Result<(), Self::Error> {
+                DISPOSED.with(|d| d.borrow_mut().push("pool"));
+                Err("connection lost")
+            }
+        }
+
+        impl IShared for Cache {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = ();
+
+            fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+                ctn.shared::<Pool>().map_err(|_| ())?;
+                Ok(Rc::new(Access::new(())))
+            }
+        }
+
+        impl IDispose for Cache {
+            fn dispose(_: &Self::Pointer) -> Result<(), Self::Error> {
+                DISPOSED.with(|d| d.borrow_mut().push("cache"));
+                Ok(())
+            }
+        }
+
+        let mut ctn = ServiceContainer::builder()
+            .with_dispose::<Pool>()
+            .with_dispose::<Cache>()
+            .build();
+        let _: Shared<Cache> = ctn.resolver().shared().unwrap();
+        let _: Shared<u32> = ctn.resolver().shared().unwrap();
+
+        let errors = ctn.dispose_all().unwrap_err();
+        assert_eq!(DISPOSED.with(|d| d.borrow().clone()), vec!["cache", "pool"]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].service, std::any::type_name::<Pool>());
+        assert_eq!(
+            errors[0].error.downcast_ref::<&str>(),
+            Some(&"connection lost")
+        );
+
+        assert!(!ctn.contains_shared::<Cache>());
+        assert!(ctn.dispose_all().is_ok());
+        assert_eq!(DISPOSED.with(|d| d.borrow().len()), 2);
+    }
+
+    #[test]
+    fn owned_pool() {
+        let constructed = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&constructed);
+        let mut ctn = ServiceContainer::builder()
+            .with_owned_constructor::<u32>(move |_, _| {
+                counter.set(counter.get() + 1);
+                Ok(counter.get())
+            })
+            .with_owned_pool::<u32>()
+            .build();
+
+        let first = ctn.resolver().owned_pooled::<u32>(()).unwrap();
+        assert_eq!(first, 1);
+        ctn.release_owned::<u32>(first);
+
+        assert_eq!(ctn.resolver().owned_pooled::<u32>(()).unwrap(), 1);
+        assert_eq!(ctn.resolver().owned_pooled::<u32>(()).unwrap(), 2);
+        assert_eq!(constructed.get(), 2);
+    }
+
+    #[test]
+    fn owned_pool_disabled() {
+        let mut ctn = ServiceContainer::new();
+        ctn.release_owned::<u32>(1);
+        assert_eq!(ctn.resolver().owned_pooled::<u32>(()).unwrap(), 2468);
+    }
+
+    #[test]
+    fn try_owned() {
+        let mut ctn = ServiceContainer::new();
+        assert_eq!(ctn.resolver().try_owned::<u32>(()), Some(2468));
+        assert!(ctn.resolver().try_owned::<Failing>(()).is_none());
+    }
+
+    #[test]
+    fn shared_fallback() {
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|_| Err(()))
+            .with_shared_fallback::<u32>(|_, _| Ok(Rc::new(Access::new(404))))
+            .build();
+        let first: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***first.inner(), 404);
+        let second: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert!(first.is(&second));
+
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_fallback::<Failing>(|_, _| Err("fallback"))
+            .build();
+        assert_eq!(ctn.resolver().shared::<Failing>().err(), Some("fallback"));
+        assert!(!ctn.contains_shared::<Failing>());
+    }
+
+    #[test]
+    fn shared_retry() {
+        let attempts = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&attempts);
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(move |_| {
+                counter.set(counter.get() + 1);
+                match counter.get() {
+                    1 | 2 => Err(()),
+                    n => Ok(Rc::new(Access::new(n))),
+                }
+            })
+            .with_shared_retry::<u32>(3, Duration::from_millis(1))
+            .build();
+        let first: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert_eq!(***first.inner(), 3);
+        let second: Shared<u32> = ctn.resolver().shared().unwrap();
+        assert!(first.is(&second));
+        assert_eq!(attempts.get(), 3);
+
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_retry::<Failing>(2, Duration::ZERO)
+            .build();
+        assert_eq!(ctn.resolver().shared::<Failing>().err(), Some("error123"));
+    }
+
+    #[test]
+    fn optional() {
+        let mut ctn = ServiceContainer::builder()
+            .with_owned_constructor::<u32>(|_, _| Ok(1))
+            .with_shared_constructor::<Failing>(|_| Err("failed"))
+            .build();
+        assert!(ctn.resolver().optional_shared::<u32>().unwrap().is_none());
+        assert!(!ctn.contains_shared::<u32>());
+        assert_eq!(ctn.resolver().optional_owned::<u32>(()), Ok(Some(1)));
+        assert_eq!(
+            ctn.resolver().optional_shared::<Failing>().err(),
+            Some("failed")
+        );
+        assert!(ctn
+            .resolver()
+            .optional_owned::<Failing>(())
+            .unwrap()
+            .is_none());
+
+        ctn.insert::<u32>(Rc::new(Access::new(2)));
+        let shared = ctn.resolver().optional_shared::<u32>().unwrap().unwrap();
+        assert_eq!(***shared.inner(), 2);
+    }
+
+    #[test]
+    fn shared_opt() {
+        let mut ctn = ServiceContainer::new();
+        assert!(ctn.resolver().shared_opt::<u32>().is_none());
+        assert!(!ctn.contains_shared::<u32>());
+
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<u32>(|_| Ok(Rc::new(Access::new(7))))
+            .with_shared_constructor::<Failing>(|_| Err("failed"))
+            .build();
+        assert!(ctn.resolver().shared_opt::<Failing>().is_none());
+        assert_eq!(ctn.take_failed_path(), None);
+
+        let shared = ctn.resolver().shared_opt::<u32>().unwrap();
+        assert_eq!(***shared.inner(), 7);
+        assert!(ctn.contains_shared::<u32>());
+    }
+
+    #[test]
+    fn resolve_stack_capacity() {
+        let ctn = ServiceContainer::builder()
+            .with_resolve_stack_capacity(32)
+            .build();
+        assert!(ctn.constructing.capacity() >= 32);
+        assert!(ctn.breadcrumbs.capacity() >= 32);
+
+        let ctn = ServiceContainer::builder().build();
+        assert!(ctn.constructing.capacity() >= 8);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn access_timeout() {
+        struct Locked;
+        impl IShared for Locked {
+            type Pointer = Arc<Mutex<u8>>;
+            type Target = u8;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Arc::new(Mutex::new(7)))
+            }
+        }
+
+        let timed_out = |result: std::thread::Result<u8>| {
+            let payload = result.unwrap_err();
+            let message = payload.downcast_ref::<String>().unwrap();
+            assert!(message.contains(std::any::type_name::<Locked>()));
+        };
+
+        let mut ctn = ServiceContainer::builder()
+            .with_access_timeout(Duration::from_millis(20))
+            .build();
+        let shared: Shared<Locked> = ctn.resolver().shared().unwrap();
+        assert_eq!(shared.access(|v| *v.assert_healthy()), 7);
+
+        let lock = shared.inner().lock().unwrap();
+        timed_out(panic::catch_unwind(AssertUnwindSafe(|| {
+            shared.access(|v| *v.assert_healthy())
+        })));
+        timed_out(panic::catch_unwind(AssertUnwindSafe(|| {
+            ctn.resolver().with_shared::<Locked, _, _>(|v| *v).unwrap()
+        })));
+
+        let mut child = ctn.child();
+        let from_child: Shared<Locked> = child.resolver().shared().unwrap();
+        timed_out(panic::catch_unwind(AssertUnwindSafe(|| {
+            from_child.access_mut(|v| *v.assert_healthy())
+        })));
+        drop(lock);
+    }
+
+    #[test]
+    fn lazy_shared() {
+        static CONSTRUCTED: AtomicUsize = AtomicUsize::new(0);
+
+        struct Settings;
+        impl IShared for Settings {
+            type Pointer = Arc<LazyLock<u32>>;
+            type Target = u32;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Arc::new(LazyLock::new(|| {
+                    CONSTRUCTED.fetch_add(1, Ordering::SeqCst);
+                    42
+                })))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let shared: Shared<Settings> = ctn.resolver().shared().unwrap();
+        assert_eq!(CONSTRUCTED.load(Ordering::SeqCst), 0);
+        assert_eq!(shared.access(|v| *v.assert_healthy()), 42);
+        assert_eq!(ctn.resolver().with_shared::<Settings, _, _>(|v| *v), Ok(42));
+        assert_eq!(CONSTRUCTED.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn once_shared() {
+        #[derive(Default)]
+        struct Settings {
+            port: u16,
+        }
+
+        struct Config;
+        impl IShared for Config {
+            type Pointer = Arc<OnceLock<Settings>>;
+            type Target = Settings;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Arc::new(OnceLock::new()))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let shared: Shared<Config> = ctn.resolver().shared().unwrap();
+        assert!(shared.inner().get().is_none());
+        assert_eq!(shared.access(|s| s.assert_healthy().port), 0);
+        assert!(shared.inner().get().is_some());
+
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<Config>(|_| {
+                Ok(Arc::new(OnceLock::from(Settings { port: 8080 })))
+            })
+            .build();
+        let port = ctn.resolver().with_shared::<Config, _, _>(|s| s.port);
+        assert_eq!(port, Ok(8080));
+    }
+
+    #[test]
+    fn owned_ref() {
+        struct Report;
+        impl IOwned for Report {
+            type Instance = usize;
+            type Parameters = Vec<u8>;
+            type Error = ();
+
+            fn construct(_: Resolver, _: Vec<u8>) -> Result<usize, ()> {
+                unreachable!("construct_ref is overridden")
+            }
+
+            fn construct_ref(_: Resolver, params: &Vec<u8>) -> Result<usize, ()> {
+                Ok(params.len())
+            }
+        }
+
+        let params = vec![1, 2, 3];
+        let mut ctn = ServiceContainer::new();
+        assert_eq!(ctn.resolver().owned_ref::<Report>(&params), Ok(3));
+        assert_eq!(ctn.resolver().owned_ref::<u32>(&()), Ok(2468));
+
+        let mut ctn = ServiceContainer::builder()
+            .with_owned_constructor::<Report>(|_, params| Ok(params.len() * 2))
+            .build();
+        assert_eq!(ctn.resolver().owned_ref::<Report>(&params), Ok(6));
+    }
+
+    #[test]
+    fn owned_default() {
+        let mut ctn = ServiceContainer::new();
+        assert_eq!(ctn.resolver().owned_default::<u32>(), Ok(2468));
+        assert_eq!(
+            ctn.resolver().owned_default::<Failing>().err(),
+            Some("error456")
+        );
+    }
+
+    #[test]
+    fn fanout() {
+        let mut ctn = ServiceContainer::new();
+        let (a, b) = ctn.resolver().fanout2::<u32, ()>().unwrap();
+        assert_eq!(***a.inner(), 1234);
+        assert!(b.is(&ctn.resolver().shared::<()>().unwrap()));
+
+        let (a, b, c) = ctn.resolver().fanout3::<u32, (), u32>().unwrap();
+        assert!(a.is(&c));
+        assert!(b.is(&ctn.resolver().shared::<()>().unwrap()));
+    }
+
+    #[test]
+    fn construction_path() {
+        let path = Rc::new(RefCell::new(Vec::new()));
+        let recorded = Rc::clone(&path);
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_constructor::<()>(|mut ctn| {
+                ctn.shared::<u32>()?;
+                Ok(Rc::new(Access::new(())))
+            })
+            .with_shared_constructor::<u32>(move |ctn| {
+                *recorded.borrow_mut() = ctn.construction_path().to_vec();
+                Ok(Rc::new(Access::new(1)))
+            })
+            .build();
+        assert!(ctn.resolver().construction_path().is_empty());
+
+        let _ = ctn.resolver().shared::<()>().unwrap();
+        assert_eq!(*path.borrow(), vec!["()", "u32"]);
+    }
+
+    #[test]
+    fn shared_checked_path() {
+        struct Root;
+        struct Middle;
+
+        impl IShared for Root {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = &'static str;
+
+            fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+                ctn.shared::<Middle>()?;
+                Ok(Rc::new(Access::new(())))
+            }
+        }
+
+        impl IShared for Middle {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = &'static str;
+
+            fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+                ctn.shared::<u32>().map_err(|_| "unreachable")?;
+                ctn.owned::<Failing>(())?;
+                Ok(Rc::new(Access::new(())))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        match ctn.resolver().shared_checked::<Root>() {
+            Err(ResolveError::Service { error, path }) => {
+                assert_eq!(error, "error456");
+                assert_eq!(
+                    path,
+                    vec![
+                        std::any::type_name::<Root>(),
+                        std::any::type_name::<Middle>(),
+                        std::any::type_name::<Failing>(),
+                    ]
+                );
+            }
+            _ => panic!("Root should fail with a service error"),
+        }
+        assert!(ctn.breadcrumbs.is_empty());
+
+        let _: Shared<u32> = ctn.resolver().shared_checked().unwrap();
+    }
+
+    #[test]
+    fn failed_path_after_recovery() {
+        struct Root;
+        struct Broken;
+
+        impl IShared for Root {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = &'static str;
+
+            fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+                assert!(ctn.try_owned::<Failing>(()).is_none());
+                ctn.shared::<Broken>()?;
+                Ok(Rc::new(Access::new(())))
+            }
+        }
+
+        impl IShared for Broken {
+            type Pointer = Rc<Access<()>>;
+            type Target = ();
+            type Error = &'static str;
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Err("broken")
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        match ctn.resolver().shared_checked::<Root>() {
+            Err(ResolveError::Service { error, path }) => {
+                assert_eq!(error, "broken");
+                assert_eq!(
+                    path,
+                    vec![
+                        std::any::type_name::<Root>(),
+                        std::any::type_name::<Broken>(),
+                    ]
+                );
+            }
+            _ => panic!("Root should fail with a service error"),
+        }
+    }
+
+    #[test]
+    fn breadcrumbs_after_panic() {
+        let mut ctn = ServiceContainer::builder()
+            .with_owned_constructor::<u32>(|_, _| panic!("constructor panicked"))
+            .build();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| ctn.resolver().owned::<u32>(())));
+        assert!(result.is_err());
+        assert!(ctn.breadcrumbs.is_empty());
+    }
+
+    #[test]
+    fn resolve_after_constructor_panic() {
+        let panicked = Cell::new(false);
+        let mut ctn = ServiceContainer::builder()
+            .with_shared_factory::<u32, _>(move |_| {
+                if !panicked.replace(true) {
+                    panic!("constructor panicked");
+                }
+                Ok(Rc::new(Access::new(5)))
+            })
+            .build();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| ctn.resolver().shared::<u32>()));
+        assert!(result.is_err());
+        assert!(ctn.constructing.is_empty());
+
+        let _: Shared<u32> = ctn.resolver().shared_checked().unwrap();
+    }
+
+    #[test]
+    fn resolved_while_borrowed() {
+        struct Counter;
+
+        impl IShared for Counter {
+            type Pointer = Rc<RefCell<u32>>;
+            type Target = u32;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(RefCell::new(0)))
+            }
+
+            fn resolved(this: &mut Self::Pointer, _: Resolver) {
+                this.try_access_mut(|count| *count.assert_healthy() += 1);
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let counter: Shared<Counter> = ctn.resolver().shared().unwrap();
+        assert_eq!(*counter.inner().borrow(), 1);
+
+        counter.access_mut(|_| {
+            let again: Shared<Counter> = ctn.resolver().shared().unwrap();
+            assert!(again.is(&counter));
+        });
+        assert_eq!(*counter.inner().borrow(), 1);
+    }
+
+    #[test]
+    fn teardown() {
+        thread_local! {
+            static TORN_DOWN: Cell<u32> = const { Cell::new(0) };
+        }
+
+        struct Pool;
+        impl IShared for Pool {
+            type Pointer = Rc<Access<Pool>>;
+            type Target = Pool;
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new(Pool)))
+            }
+            fn teardown(this: &mut Self::Pointer, _: Resolver) {
+                // The container still holds its pointer during the hook.
+                assert_eq!(Rc::strong_count(this), 3);
+                TORN_DOWN.with(|n| n.set(n.get() + 1));
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let pool: Shared<Pool> = ctn.resolver().shared().unwrap();
+        drop(ctn);
+        assert_eq!(TORN_DOWN.with(Cell::get), 1);
+        assert_eq!(Rc::strong_count(pool.inner()), 1);
+        drop(pool);
+        assert_eq!(TORN_DOWN.with(Cell::get), 1);
+
+        let mut ctn = ServiceContainer::new();
+        let _ = ctn.resolver().shared::<Pool>().unwrap();
+        let removed = ctn.remove_shared::<Pool>();
+        drop(ctn);
+        drop(removed);
+        assert_eq!(TORN_DOWN.with(Cell::get), 1);
+    }
+
+    #[test]
+    fn teardown_when_released() {
+        thread_local! {
+            static TORN_DOWN: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+        }
+
+        struct Alpha;
+        struct Beta;
+        impl IShared for Alpha {
+            type Pointer = Rc<Access<&'static str>>;
+            type Target = &'static str;
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new("alpha")))
+            }
+            fn teardown(this: &mut Self::Pointer, _: Resolver) {
+                TORN_DOWN.with(|t| t.borrow_mut().push(***this));
+            }
+        }
+        impl IShared for Beta {
+            type Pointer = Rc<Access<&'static str>>;
+            type Target = &'static str;
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new("beta")))
+            }
+            fn teardown(this: &mut Self::Pointer, _: Resolver) {
+                TORN_DOWN.with(|t| t.borrow_mut().push(***this));
+            }
+        }
+        let torn_down = || TORN_DOWN.with(|t| t.take());
+
+        let mut ctn = ServiceContainer::new();
+        ctn.insert::<Alpha>(Rc::new(Access::new("reset")));
+        assert!(ctn.reset_shared::<Alpha>());
+        assert_eq!(torn_down(), vec!["reset"]);
+
+        let checkpoint = ctn.checkpoint();
+        let _: Shared<Alpha> = ctn.resolver().shared().unwrap();
+        ctn.restore(checkpoint);
+        assert_eq!(torn_down(), vec!["alpha"]);
+
+        let mut other = ServiceContainer::new();
+        other.insert::<Alpha>(Rc::new(Access::new("kept")));
+        let _: Shared<Alpha> = ctn.resolver().shared().unwrap();
+        ctn.merge_from(other, MergeStrategy::KeepFirst);
+        assert_eq!(torn_down(), vec!["kept"]);
+
+        let mut other = ServiceContainer::new();
+        other.insert::<Alpha>(Rc::new(Access::new("last")));
+        ctn.merge_from(other, MergeStrategy::KeepLast);
+        assert_eq!(torn_down(), vec!["alpha"]);
+
+        // Beta was stored first in its own container, but after `last` in
+        // the merged one.
+        let mut other = ServiceContainer::new();
+        let _: Shared<Beta> = other.resolver().shared().unwrap();
+        ctn.merge_from(other, MergeStrategy::Panic);
+        drop(ctn);
+        assert_eq!(torn_down(), vec!["beta", "last"]);
+    }
+
+    #[test]
+    #[cfg(feature = "test-util")]
+    fn teardown_when_replaced() {
+        thread_local! {
+            static TORN_DOWN: Cell<u32> = const { Cell::new(0) };
+        }
+
+        struct Pool;
+        impl IShared for Pool {
+            type Pointer = Rc<Access<u32>>;
+            type Target = u32;
+            type Error = ();
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new(1)))
+            }
+            fn teardown(this: &mut Self::Pointer, _: Resolver) {
+                TORN_DOWN.with(|n| n.set(***this));
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let _: Shared<Pool> = ctn.resolver().shared().unwrap();
+        let old = ctn.replace_shared::<Pool>(Rc::new(Access::new(2))).unwrap();
+        assert_eq!(**old, 1);
+        assert_eq!(TORN_DOWN.with(Cell::get), 1);
+        drop(ctn);
+        assert_eq!(TORN_DOWN.with(Cell::get), 2);
+    }
 }
@@ -0,0 +1,227 @@
+//! Errors that can occur while resolving services.
+
+use std::any::{Any, TypeId};
+use std::error::Error;
+use std::fmt;
+
+/// An error that occurred while resolving a service.
+///
+/// Returned by [`Resolver::shared_checked()`](crate::Resolver::shared_checked).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError<E> {
+    /// The constructor of a service failed.
+    Service {
+        /// The error of the service that was resolved.
+        error: E,
+        /// The type names of the services that were being constructed when
+        /// the error occurred, from the resolved service to the service whose
+        /// constructor failed.
+        path: Vec<&'static str>,
+    },
+    /// A service depends on itself, directly or through other services.
+    CyclicDependency {
+        /// The `TypeId` and type name of the services in the cycle, starting
+        /// and ending with the same service.
+        chain: Vec<(TypeId, &'static str)>,
+    },
+}
+
+/// A stored service that doesn't match its registration, see
+/// [`ServiceContainer::validate()`](crate::ServiceContainer::validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The type name of the service.
+    pub service: &'static str,
+    /// The type name of the pointer that was registered.
+    pub expected: &'static str,
+    /// The type name of the pointer that is stored, or that the custom
+    /// constructor returns if there is no instance yet.
+    pub found: &'static str,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` stores a `{}`, but was registered with `{}`",
+            self.service, self.found, self.expected
+        )
+    }
+}
+
+/// A dependency that must be registered but is not, see [`ContainerBuilder::try_build()`](crate::ContainerBuilder::try_build).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The `TypeId` of the missing dependency.
+    pub type_id: TypeId,
+    /// The type name of the missing dependency.
+    pub type_name: Option<&'static str>,
+    /// The type name of the service whose constructor needs the dependency.
+    pub required_by: &'static str,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.type_name {
+            Some(name) => write!(f, "`{}` depends on `{}`", self.required_by, name)?,
+            None => write!(f, "`{}` depends on {:?}", self.required_by, self.type_id)?,
+        }
+        write!(f, ", which is not registered")
+    }
+}
+
+impl Error for ValidationError {}
+
+/// The error of [`ContainerBuilder::try_build()`](crate::ContainerBuilder::try_build).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// Dependencies that must be registered but are not. Nothing is
+    /// constructed in this case.
+    MissingDependencies(Vec<ValidationError>),
+    /// Shared services whose construction failed.
+    Construction(Vec<PreloadError>),
+}
+
+impl BuildError {
+    /// Returns the `TypeId`s of the missing dependencies or of the services
+    /// that failed to construct.
+    pub fn type_ids(&self) -> Vec<TypeId> {
+        match self {
+            Self::MissingDependencies(errors) => errors.iter().map(|e| e.type_id).collect(),
+            Self::Construction(errors) => errors.iter().map(|e| e.type_id).collect(),
+        }
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<_> = match self {
+            Self::MissingDependencies(errors) => errors.iter().map(|e| e.to_string()).collect(),
+            Self::Construction(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        };
+        write!(f, "{}", errors.join("; "))
+    }
+}
+
+impl Error for BuildError {}
+
+/// A service that failed to construct, see
+/// [`ServiceContainer::preload()`](crate::ServiceContainer::preload).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreloadError {
+    /// The `TypeId` of the service.
+    pub type_id: TypeId,
+    /// The type names of the services that were being constructed when the
+    /// error occurred, from the preloaded service to the service whose
+    /// constructor failed.
+    pub path: Vec<&'static str>,
+}
+
+impl fmt::Display for PreloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to construct `{}`", self.path.join("` -> `"))
+    }
+}
+
+impl Error for PreloadError {}
+
+/// The error of a service that failed to shut down, see
+/// [`ServiceContainer::dispose_all()`](crate::ServiceContainer::dispose_all).
+pub struct DisposeError {
+    /// The type name of the service.
+    pub service: &'static str,
+    /// The `IShared::Error` of the service, which can be recovered with
+    /// `downcast`.
+    pub error: Box<dyn Any>,
+}
+
+impl fmt::Debug for DisposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DisposeError")
+            .field("service", &self.service)
+            .finish()
+    }
+}
+
+impl fmt::Display for DisposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to dispose `{}`", self.service)
+    }
+}
+
+impl Error for DisposeError {}
+
+impl<E> ResolveError<E> {
+    /// Returns the error of the service, if there is one.
+    pub fn into_service_error(self) -> Option<E> {
+        match self {
+            Self::Service { error, .. } => Some(error),
+            Self::CyclicDependency { .. } => None,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ResolveError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Service { error, path } => {
+                write!(
+                    f,
+                    "failed to construct `{}`: {}",
+                    path.join("` -> `"),
+                    error
+                )
+            }
+            Self::CyclicDependency { chain } => {
+                let names: Vec<_> = chain.iter().map(|(_, name)| *name).collect();
+                write!(f, "circular dependency: `{}`", names.join("` -> `"))
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ResolveError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Service { error, .. } => Some(error),
+            Self::CyclicDependency { .. } => None,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let error = ResolveError::Service {
+            error: "connection refused",
+            path: vec!["Server", "Database"],
+        };
+        assert_eq!(
+            error.to_string(),
+            "failed to construct `Server` -> `Database`: connection refused"
+        );
+
+        let chicken = (TypeId::of::<u8>(), "Chicken");
+        let egg = (TypeId::of::<u16>(), "Egg");
+        let error = ResolveError::<&str>::CyclicDependency {
+            chain: vec![chicken, egg, chicken],
+        };
+        assert_eq!(
+            error.to_string(),
+            "circular dependency: `Chicken` -> `Egg` -> `Chicken`"
+        );
+
+        let error = DisposeError {
+            service: "Pool",
+            error: Box::new("connection lost"),
+        };
+        assert_eq!(error.to_string(), "failed to dispose `Pool`");
+    }
+}
@@ -1,26 +1,78 @@
 //! Wrapper types to get and store services.
 
-use super::access::{IAccess, IAccessMut, Poisoning};
-use super::pointers::ISharedPointer;
+use super::access::{access_mut_within, access_timeout, access_within};
+use super::access::{Access, IAccess, IAccessMut, Observable, Poisoning};
+use super::pointers::{ISharedPointer, IWeakPointer};
 use super::service_traits::{IOwned, IShared};
+use crate::Resolver;
+use std::any::Any;
+use std::cell::RefCell;
 use std::fmt;
-use std::ops::Deref;
+use std::hash::{Hash, Hasher};
+use std::ops::{ControlFlow, Deref, DerefMut};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::Duration;
 
 ///////////////////////////////////////////////////////////////////////////////
 // Shared Instance
 ///////////////////////////////////////////////////////////////////////////////
 
 /// A pointer to a shared instance from the service container.
-#[repr(transparent)]
+///
+/// In debug builds, blocking access such as [`access`](Shared::access)
+/// panics with the type name of the service when it waits too long for a
+/// lock, to turn a deadlock into a panic. The timeout is 30 seconds, unless
+/// the container sets another one, see
+/// [`ContainerBuilder::with_access_timeout()`]. Release builds wait as long
+/// as it takes.
+///
+/// [`ContainerBuilder::with_access_timeout()`]: crate::ContainerBuilder::with_access_timeout
 pub struct Shared<S: ?Sized + IShared> {
     /// The actual smart pointer to the shared instance.
     inner: S::Pointer,
+    /// How long blocking access waits for a lock before it panics.
+    timeout: Option<Duration>,
 }
 
 impl<S: ?Sized + IShared> Shared<S> {
     /// Creates a shared instance from the inner smart pointer.
     pub fn new(inner: S::Pointer) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            timeout: access_timeout(None),
+        }
+    }
+
+    /// Sets how long blocking access waits for a lock before it panics.
+    pub(crate) fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Creates a shared instance of a read-only service, whose pointer is
+    /// `Rc<Access<T>>` or `Arc<Access<T>>`.
+    ///
+    /// ```rust
+    /// # use rscontainer::{Access, IShared, Resolver, Shared};
+    /// # use std::rc::Rc;
+    /// struct Port;
+    ///
+    /// impl IShared for Port {
+    ///     type Pointer = Rc<Access<u16>>;
+    ///     type Target = u16;
+    ///     type Error = ();
+    ///
+    ///     fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+    ///         Ok(Shared::<Self>::from_value(8080).into_inner())
+    ///     }
+    /// }
+    /// ```
+    pub fn from_value<T>(value: T) -> Self
+    where
+        S::Pointer: From<Access<T>>,
+    {
+        Self::new(Access::new(value).into())
     }
 
     /// Returns the inner smart pointer of the shared instance.
@@ -46,13 +98,38 @@ impl<S: ?Sized + IShared> Shared<S> {
         self.inner.ptr_eq(other.inner())
     }
 
+    /// Returns true if two shared instances point to the same instance, see
+    /// [`is`](Shared::is).
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.is(other)
+    }
+
+    /// Returns the number of strong pointers to the shared instance,
+    /// including the one in the container.
+    pub fn strong_count(&self) -> usize {
+        self.inner.strong_count()
+    }
+
+    /// Converts the shared instance into a type-erased `Arc`.
+    ///
+    /// This makes it possible to store different kinds of shared services
+    /// in a single collection. The original pointer can be recovered with
+    /// [`Arc::downcast`].
+    pub fn into_any<T>(self) -> Arc<dyn Any + Send + Sync>
+    where
+        S: IShared<Pointer = Arc<T>>,
+        T: Any + Send + Sync,
+    {
+        self.inner
+    }
+
     /// Get access to the shared instance through a closure.
     pub fn access<U, F>(&self, f: F) -> U
     where
         S::Pointer: IAccess,
         F: FnOnce(Poisoning<&<S::Pointer as IAccess>::Target>) -> U,
     {
-        self.inner.access(f)
+        access_within::<S, _, _, _>(&self.inner, self.timeout, f)
     }
 
     /// Get access to the shared instance through a closure.
@@ -64,13 +141,23 @@ impl<S: ?Sized + IShared> Shared<S> {
         self.inner.try_access(f)
     }
 
+    /// Get access to the shared instance through a closure, and wait at most
+    /// `timeout` for a lock. Returns `None` if the access failed.
+    pub fn try_access_for<U, F>(&self, timeout: Duration, f: F) -> Option<U>
+    where
+        S::Pointer: IAccess,
+        F: FnOnce(Poisoning<&<S::Pointer as IAccess>::Target>) -> U,
+    {
+        self.inner.try_access_for(timeout, f)
+    }
+
     /// Get access to the shared instance through a closure.
     pub fn access_mut<U, F>(&self, f: F) -> U
     where
         S::Pointer: IAccessMut,
         F: FnOnce(Poisoning<&mut <S::Pointer as IAccess>::Target>) -> U,
     {
-        self.inner.access_mut(f)
+        access_mut_within::<S, _, _, _>(&self.inner, self.timeout, f)
     }
 
     /// Get access to the shared instance through a closure.
@@ -81,6 +168,125 @@ impl<S: ?Sized + IShared> Shared<S> {
     {
         self.inner.try_access_mut(f)
     }
+
+    /// Projects a value out of the shared instance, and returns it with the
+    /// poisoning state of the instance.
+    ///
+    /// Unlike [`snapshot`], this doesn't panic if the instance is poisoned,
+    /// so the caller decides how to handle poisoning.
+    ///
+    /// [`snapshot`]: Shared::snapshot
+    pub fn with<U, F>(&self, f: F) -> Poisoning<U>
+    where
+        F: FnOnce(&S::Target) -> U,
+    {
+        self.access(|instance| instance.map(f))
+    }
+
+    /// Projects a value out of the shared instance through a mutable
+    /// reference, see [`with`](Shared::with).
+    pub fn with_mut<U, F>(&self, f: F) -> Poisoning<U>
+    where
+        S::Pointer: IAccessMut,
+        F: FnOnce(&mut S::Target) -> U,
+    {
+        self.access_mut(|instance| instance.map(f))
+    }
+
+    /// Takes a snapshot of the shared instance and releases the lock before
+    /// returning it.
+    ///
+    /// Use this to keep lock hold time short when a service is contended:
+    /// only copy out what you need inside the closure and do any expensive
+    /// computation on the returned value afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    pub fn snapshot<T, F>(&self, f: F) -> T
+    where
+        S::Pointer: IAccess,
+        F: FnOnce(&<S::Pointer as IAccess>::Target) -> T,
+    {
+        self.access(|instance| f(instance.assert_healthy()))
+    }
+
+    /// Tries to take a snapshot of the shared instance, see [`snapshot`].
+    ///
+    /// Returns `None` if the instance is currently locked or mutably
+    /// borrowed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    ///
+    /// [`snapshot`]: Shared::snapshot
+    pub fn try_snapshot<T, F>(&self, f: F) -> Option<T>
+    where
+        S::Pointer: IAccess,
+        F: FnOnce(&<S::Pointer as IAccess>::Target) -> T,
+    {
+        self.inner
+            .try_access(|instance| f(instance.assert_healthy()))
+    }
+
+    /// Clones the whole shared instance out and releases the lock before
+    /// returning it, see [`snapshot`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    ///
+    /// [`snapshot`]: Shared::snapshot
+    pub fn snapshot_clone(&self) -> S::Target
+    where
+        S::Target: Clone,
+    {
+        self.snapshot(S::Target::clone)
+    }
+
+    /// Clones the whole shared instance out, and keeps the poisoning state
+    /// instead of panicking.
+    pub fn snapshot_clone_checked(&self) -> Poisoning<S::Target>
+    where
+        S::Target: Clone,
+    {
+        self.access(|instance| instance.map(S::Target::clone))
+    }
+
+    /// Collects the items of an iterator over the shared instance into a
+    /// `Vec`, and releases the lock before returning it.
+    ///
+    /// The closure returns a boxed iterator, because the iterator borrows
+    /// the instance, which can't be expressed with a generic return type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    pub fn iter_collect<T, F>(&self, f: F) -> Vec<T>
+    where
+        S::Pointer: IAccess,
+        F: FnOnce(&<S::Pointer as IAccess>::Target) -> Box<dyn Iterator<Item = T> + '_>,
+    {
+        self.snapshot(|instance| f(instance).collect())
+    }
+
+    /// Get access to the shared instance through a closure that decides
+    /// whether the caller should continue or stop early.
+    ///
+    /// Useful for search-then-stop flows over shared state, for example when
+    /// iterating over multiple services and breaking on the first match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    pub fn access_cf<B, F>(&self, f: F) -> ControlFlow<B>
+    where
+        S::Pointer: IAccess,
+        F: FnOnce(&<S::Pointer as IAccess>::Target) -> ControlFlow<B>,
+    {
+        self.access(|instance| f(instance.assert_healthy()))
+    }
 }
 
 impl<S: ?Sized + IShared> Deref for Shared<S>
@@ -94,6 +300,19 @@ where
     }
 }
 
+impl<S: ?Sized + IShared> AsRef<S::Target> for Shared<S>
+where
+    S::Pointer: Deref<Target = Access<S::Target>>,
+{
+    /// Returns a reference to the instance, without going through `access`.
+    ///
+    /// Only available for lock-free pointers such as `Rc<Access<T>>` and
+    /// `Arc<Access<T>>`, which can't be poisoned.
+    fn as_ref(&self) -> &S::Target {
+        self.inner.deref()
+    }
+}
+
 impl<S: ?Sized + IShared> Clone for Shared<S> {
     /// Clones the pointer to the shared instance.
     ///
@@ -105,10 +324,28 @@ impl<S: ?Sized + IShared> Clone for Shared<S> {
     fn clone(&self) -> Self {
         Shared {
             inner: self.inner.clone(),
+            timeout: self.timeout,
         }
     }
 }
 
+/// Compares the pointers, like [`Shared::is()`], not the instances.
+impl<S: ?Sized + IShared> PartialEq for Shared<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.is(other)
+    }
+}
+
+impl<S: ?Sized + IShared> Eq for Shared<S> {}
+
+/// Hashes the address of the instance, not the instance itself, so that
+/// handles to the same instance can be deduplicated in a `HashSet`.
+impl<S: ?Sized + IShared> Hash for Shared<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.addr().hash(state)
+    }
+}
+
 impl<S: ?Sized + IShared> fmt::Debug for Shared<S>
 where
     S::Pointer: fmt::Debug,
@@ -120,6 +357,194 @@ where
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+// Weak Shared Instance
+///////////////////////////////////////////////////////////////////////////////
+
+/// A weak pointer to a shared instance from the service container.
+///
+/// Does not keep the instance alive, so it can be used to break reference
+/// cycles between services. As long as the container holds the instance,
+/// upgrading succeeds.
+pub struct WeakShared<S: ?Sized + IShared>
+where
+    S::Pointer: IWeakPointer,
+{
+    /// The weak counterpart of the smart pointer.
+    inner: <S::Pointer as IWeakPointer>::Weak,
+}
+
+impl<S: ?Sized + IShared> WeakShared<S>
+where
+    S::Pointer: IWeakPointer,
+{
+    /// Creates a weak pointer to the shared instance.
+    pub fn new(shared: &Shared<S>) -> Self {
+        Self {
+            inner: shared.inner.downgrade(),
+        }
+    }
+
+    /// Returns the shared instance, or `None` if it has been dropped.
+    pub fn upgrade(&self) -> Option<Shared<S>> {
+        S::Pointer::upgrade(&self.inner).map(Shared::new)
+    }
+
+    /// Returns a reference to the inner weak pointer.
+    pub fn inner(&self) -> &<S::Pointer as IWeakPointer>::Weak {
+        &self.inner
+    }
+}
+
+impl<S: ?Sized + IShared> Shared<S>
+where
+    S::Pointer: IWeakPointer,
+{
+    /// Creates a weak pointer to the shared instance, see [`WeakShared`].
+    pub fn downgrade(&self) -> WeakShared<S> {
+        WeakShared::new(self)
+    }
+}
+
+impl<S: ?Sized + IShared> Shared<S> {
+    /// Returns a receiver that gets a message each time the instance is
+    /// mutably accessed, see [`Observable`].
+    pub fn subscribe<T: ?Sized>(&self) -> Receiver<()>
+    where
+        S::Pointer: Deref<Target = Observable<T>>,
+    {
+        self.inner.subscribe()
+    }
+}
+
+impl<S: ?Sized + IShared> Clone for WeakShared<S>
+where
+    S::Pointer: IWeakPointer,
+{
+    /// Clones the weak pointer, which does not keep the instance alive.
+    fn clone(&self) -> Self {
+        WeakShared {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S: ?Sized + IShared> fmt::Debug for WeakShared<S>
+where
+    S::Pointer: IWeakPointer,
+    <S::Pointer as IWeakPointer>::Weak: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakShared")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Lazy Shared Instance
+///////////////////////////////////////////////////////////////////////////////
+
+/// A shared instance that is only resolved when it's used for the first
+/// time, see [`Resolver::lazy_shared()`].
+///
+/// It doesn't borrow the container, so the container has to be passed to
+/// [`get()`](Lazy::get) through a resolver.
+pub struct Lazy<S: ?Sized + IShared> {
+    /// The shared instance, once it has been resolved.
+    instance: RefCell<Option<Shared<S>>>,
+}
+
+impl<S: ?Sized + IShared + 'static> Lazy<S> {
+    /// Creates a lazy shared instance that isn't resolved yet.
+    pub fn new() -> Self {
+        Self {
+            instance: RefCell::new(None),
+        }
+    }
+
+    /// Resolves the shared instance the first time, and returns the same
+    /// instance every next time.
+    pub fn get(&self, mut ctn: Resolver) -> Result<Shared<S>, S::Error> {
+        if let Some(instance) = &*self.instance.borrow() {
+            return Ok(instance.clone());
+        }
+        let instance = ctn.shared::<S>()?;
+        *self.instance.borrow_mut() = Some(instance.clone());
+        Ok(instance)
+    }
+
+    /// Returns true if the shared instance has been resolved.
+    pub fn is_resolved(&self) -> bool {
+        self.instance.borrow().is_some()
+    }
+}
+
+impl<S: ?Sized + IShared + 'static> Default for Lazy<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: ?Sized + IShared> fmt::Debug for Lazy<S>
+where
+    S::Pointer: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lazy")
+            .field("instance", &self.instance)
+            .finish()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Local Instance
+///////////////////////////////////////////////////////////////////////////////
+
+/// An owned instance from the service container, see [`Resolver::local()`].
+///
+/// The counterpart of [`Shared`] for owned instances. Dereferences to the
+/// instance. Use [`Resolver::owned()`] to get the bare instance instead.
+pub struct Local<S: ?Sized + IOwned> {
+    /// The owned instance.
+    inner: S::Instance,
+}
+
+impl<S: ?Sized + IOwned> Local<S> {
+    /// Creates a local instance from an owned instance.
+    pub fn new(inner: S::Instance) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the owned instance.
+    pub fn into_inner(self) -> S::Instance {
+        self.inner
+    }
+}
+
+impl<S: ?Sized + IOwned> Deref for Local<S> {
+    type Target = S::Instance;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<S: ?Sized + IOwned> DerefMut for Local<S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<S: ?Sized + IOwned> fmt::Debug for Local<S>
+where
+    S::Instance: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Local").field(&self.inner).finish()
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // Any Kind Instance
 ///////////////////////////////////////////////////////////////////////////////
@@ -144,6 +569,48 @@ impl<S: ?Sized + IShared + IOwned> Instance<S> {
         Self::Owned(inner)
     }
 
+    /// Returns true if this is a shared instance.
+    pub fn is_shared(&self) -> bool {
+        matches!(self, Self::Shared(..))
+    }
+
+    /// Returns true if this is an owned instance.
+    pub fn is_owned(&self) -> bool {
+        matches!(self, Self::Owned(..))
+    }
+
+    /// Returns the shared instance, or `None` if this is an owned instance.
+    pub fn into_shared(self) -> Option<Shared<S>> {
+        match self {
+            Self::Shared(s) => Some(Shared::new(s)),
+            Self::Owned(..) => None,
+        }
+    }
+
+    /// Returns the owned instance, or `None` if this is a shared instance.
+    pub fn into_owned(self) -> Option<S::Instance> {
+        match self {
+            Self::Owned(l) => Some(l),
+            Self::Shared(..) => None,
+        }
+    }
+
+    /// Returns the owned instance, or a clone of the shared instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    pub fn into_owned_value(self) -> S::Instance
+    where
+        S::Instance: Clone,
+        S::Pointer: IAccess<Target = S::Instance>,
+    {
+        match self {
+            Self::Shared(s) => s.access(|s| s.assert_healthy().clone()),
+            Self::Owned(l) => l,
+        }
+    }
+
     /// Get access to the shared instance through a closure.
     pub fn access<U, F>(&self, accessor: F) -> U
     where
@@ -200,8 +667,25 @@ impl<S: ?Sized + IShared + IOwned> Instance<S> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::Access;
+    use crate::{Resolver, ServiceContainer};
     use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    struct Numbers;
+
+    impl IShared for Numbers {
+        type Pointer = Arc<Mutex<Vec<u32>>>;
+        type Target = Vec<u32>;
+        type Error = ();
+
+        fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+            Ok(Arc::new(Mutex::new(vec![1, 2, 3])))
+        }
+    }
+
+    fn numbers() -> Shared<Numbers> {
+        Shared::new(Arc::new(Mutex::new(vec![1, 2, 3])))
+    }
 
     #[test]
     fn shared_is() {
@@ -210,4 +694,367 @@ mod test {
 
         assert!(s1.is(&s2));
     }
+
+    #[test]
+    fn ptr_eq_and_strong_count() {
+        let mut ctn = ServiceContainer::new();
+        let s1: Shared<Numbers> = ctn.resolver().shared().unwrap();
+        let s2: Shared<Numbers> = ctn.resolver().shared().unwrap();
+        assert!(Shared::ptr_eq(&s1, &s2));
+        assert!(!Shared::ptr_eq(&s1, &numbers()));
+        assert_eq!(s1.strong_count(), 3);
+
+        drop(s2);
+        assert_eq!(s1.strong_count(), 2);
+        drop(ctn);
+        assert_eq!(s1.strong_count(), 1);
+    }
+
+    #[test]
+    fn local() {
+        let mut ctn = ServiceContainer::new();
+        let mut local: Local<u32> = ctn.resolver().local(()).unwrap();
+        assert_eq!(*local, 2468);
+        *local += 1;
+        assert_eq!(local.into_inner(), 2469);
+    }
+
+    #[test]
+    fn snapshot_releases_lock() {
+        let shared = numbers();
+        let snapshot = shared.snapshot(|numbers| numbers.clone());
+
+        // The heavy computation runs on the snapshot, without the lock.
+        assert!(shared.inner().try_lock().is_ok());
+        assert_eq!(snapshot.iter().sum::<u32>(), 6);
+    }
+
+    #[test]
+    fn snapshot_clone() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Config {
+            name: String,
+            retries: u32,
+        }
+
+        struct Settings;
+
+        impl IShared for Settings {
+            type Pointer = Arc<Mutex<Config>>;
+            type Target = Config;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                unreachable!()
+            }
+        }
+
+        let config = Config {
+            name: "app".into(),
+            retries: 3,
+        };
+        let shared = Shared::<Settings>::new(Arc::new(Mutex::new(config.clone())));
+        assert_eq!(shared.snapshot_clone(), config);
+        assert_eq!(shared.snapshot_clone_checked().ok(), Some(config.clone()));
+
+        let poisoner = shared.clone();
+        let _ = std::thread::spawn(move || poisoner.access_mut(|_| panic!())).join();
+        assert_eq!(shared.snapshot_clone_checked().err(), Some(config));
+    }
+
+    #[test]
+    fn with() {
+        let shared = numbers();
+        assert_eq!(shared.with(|numbers| numbers[1]).ok(), Some(2));
+        assert_eq!(shared.with_mut(|numbers| numbers.pop()).ok(), Some(Some(3)));
+
+        let poisoner = shared.clone();
+        let _ = std::thread::spawn(move || poisoner.access_mut(|_| panic!())).join();
+        assert_eq!(shared.with(|numbers| numbers.len()).err(), Some(2));
+        assert_eq!(shared.with_mut(|numbers| numbers.len()).err(), Some(2));
+    }
+
+    #[test]
+    fn try_snapshot() {
+        let shared = numbers();
+        assert_eq!(shared.try_snapshot(|numbers| numbers.len()), Some(3));
+
+        let _guard = shared.inner().lock().unwrap();
+        assert_eq!(shared.try_snapshot(|numbers| numbers.len()), None);
+    }
+
+    #[test]
+    fn access_cf() {
+        let shared = numbers();
+
+        let flow = shared.access_cf(|numbers| match numbers.iter().find(|n| **n == 2) {
+            Some(n) => ControlFlow::Break(*n),
+            None => ControlFlow::Continue(()),
+        });
+        assert_eq!(flow, ControlFlow::Break(2));
+
+        let flow = shared.access_cf(|numbers| match numbers.iter().find(|n| **n == 9) {
+            Some(n) => ControlFlow::Break(*n),
+            None => ControlFlow::Continue(()),
+        });
+        assert_eq!(flow, ControlFlow::Continue(()));
+    }
+
+    #[test]
+    fn into_any() {
+        struct Name;
+
+        impl IShared for Name {
+            type Pointer = Arc<Access<&'static str>>;
+            type Target = &'static str;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Arc::new(Access::new("name")))
+            }
+        }
+
+        let services: Vec<Arc<dyn Any + Send + Sync>> = vec![
+            numbers().into_any(),
+            Shared::<Name>::new(Arc::new(Access::new("name"))).into_any(),
+        ];
+
+        let numbers = Arc::clone(&services[0])
+            .downcast::<Mutex<Vec<u32>>>()
+            .unwrap();
+        assert_eq!(*numbers.lock().unwrap(), vec![1, 2, 3]);
+
+        let name = Arc::clone(&services[1])
+            .downcast::<Access<&'static str>>()
+            .unwrap();
+        assert_eq!(**name, "name");
+    }
+
+    #[test]
+    fn as_ref_lock_free() {
+        struct Name;
+
+        impl IShared for Name {
+            type Pointer = Arc<Access<String>>;
+            type Target = String;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Arc::new(Access::new(String::from("rscontainer"))))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let name: Shared<Name> = ctn.resolver().shared().unwrap();
+        let name_ref: &String = name.as_ref();
+        assert_eq!(name_ref, "rscontainer");
+    }
+
+    #[test]
+    fn weak_shared() {
+        let mut ctn = ServiceContainer::new();
+        let weak = ctn.resolver().weak_shared::<Numbers>().unwrap();
+        let shared = weak.upgrade().unwrap();
+        assert_eq!(Arc::strong_count(shared.inner()), 2);
+        assert!(shared.is(&ctn.resolver().shared().unwrap()));
+
+        drop(ctn);
+        drop(shared);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn iter_collect() {
+        let numbers = numbers();
+        let doubled = numbers.iter_collect(|n| Box::new(n.iter().map(|n| n * 2)));
+        assert_eq!(doubled, vec![2, 4, 6]);
+        assert!(numbers.inner().try_lock().is_ok());
+    }
+
+    #[test]
+    fn from_value() {
+        let shared = Shared::<u32>::from_value(5);
+        assert_eq!(***shared.inner(), 5);
+
+        struct Name;
+        impl IShared for Name {
+            type Pointer = Arc<Access<&'static str>>;
+            type Target = &'static str;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Shared::<Self>::from_value("name").into_inner())
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let name = ctn.resolver().shared::<Name>().unwrap();
+        assert_eq!(name.access(|n| *n.assert_healthy()), "name");
+    }
+
+    #[test]
+    fn try_access_for() {
+        let numbers = numbers();
+        let timeout = Duration::from_millis(10);
+        let lock = numbers.inner().lock().unwrap();
+        assert!(numbers.try_access_for(timeout, |_| ()).is_none());
+        drop(lock);
+        let len = numbers.try_access_for(timeout, |n| n.assert_healthy().len());
+        assert_eq!(len, Some(3));
+    }
+
+    #[test]
+    fn downgrade_breaks_cycle() {
+        use std::cell::RefCell;
+
+        struct Parent;
+        struct Child;
+
+        impl IShared for Parent {
+            type Pointer = Rc<Access<RefCell<Option<Shared<Child>>>>>;
+            type Target = RefCell<Option<Shared<Child>>>;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Access::new(RefCell::new(None))))
+            }
+        }
+
+        impl IShared for Child {
+            type Pointer = Rc<Access<WeakShared<Parent>>>;
+            type Target = WeakShared<Parent>;
+            type Error = ();
+
+            fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+                let parent: Shared<Parent> = ctn.shared()?;
+                Ok(Rc::new(Access::new(parent.downgrade())))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let child: Shared<Child> = ctn.resolver().shared().unwrap();
+        let parent: Shared<Parent> = ctn.resolver().shared().unwrap();
+        *parent.borrow_mut() = Some(child.clone());
+        assert!(child.as_ref().upgrade().unwrap().is(&parent));
+
+        drop(ctn);
+        drop(parent);
+        assert!(child.as_ref().upgrade().is_none());
+        assert_eq!(Rc::strong_count(child.inner()), 1);
+    }
+
+    #[test]
+    fn hash_set() {
+        use std::collections::HashSet;
+
+        let s1 = Shared::<u32>::new(Rc::new(Access::new(100)));
+        let s2 = Shared::<u32>::new(Rc::new(Access::new(100)));
+
+        let set: HashSet<_> = vec![s1.clone(), s1.clone(), s2.clone()]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&s1));
+        assert!(s1 != s2);
+    }
+
+    #[test]
+    fn instance_variants() {
+        let mut ctn = ServiceContainer::new();
+
+        let shared = ctn.resolver().shared_instance::<u32>().unwrap();
+        assert!(shared.is_shared());
+        assert!(!shared.is_owned());
+        assert_eq!(shared.access(|n| *n.assert_healthy()), 1234);
+        assert!(shared.into_shared().is_some());
+
+        let owned = ctn.resolver().owned_instance::<u32>(()).unwrap();
+        assert!(owned.is_owned());
+        assert_eq!(owned.access(|n| *n.assert_healthy()), 2468);
+        assert_eq!(owned.into_owned(), Some(2468));
+    }
+
+    #[test]
+    fn into_owned_value() {
+        let mut ctn = ServiceContainer::new();
+
+        let shared = ctn.resolver().shared_instance::<u32>().unwrap();
+        assert_eq!(shared.into_owned_value(), 1234);
+
+        let owned = ctn.resolver().owned_instance::<u32>(()).unwrap();
+        assert_eq!(owned.into_owned_value(), 2468);
+    }
+
+    #[test]
+    fn shared_get() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Address {
+            port: u16,
+        }
+
+        struct Server {
+            name: String,
+            address: Address,
+        }
+
+        impl IShared for Server {
+            type Pointer = Arc<Mutex<Server>>;
+            type Target = Server;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Arc::new(Mutex::new(Server {
+                    name: "api".into(),
+                    address: Address { port: 8080 },
+                })))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let server: Shared<Server> = ctn.resolver().shared().unwrap();
+        assert_eq!(crate::shared_get!(server.name), "api");
+        assert_eq!(crate::shared_get!(server.address), Address { port: 8080 });
+        assert_eq!(crate::shared_get!(server.address.port), 8080);
+        assert!(server.inner().try_lock().is_ok());
+    }
+
+    #[test]
+    fn lazy() {
+        let mut ctn = ServiceContainer::new();
+        let lazy: Lazy<Numbers> = ctn.resolver().lazy_shared();
+        assert!(!lazy.is_resolved());
+        assert!(!ctn.contains_shared::<Numbers>());
+
+        let first = lazy.get(ctn.resolver()).unwrap();
+        assert!(lazy.is_resolved());
+        assert!(ctn.contains_shared::<Numbers>());
+
+        ctn.reset_shared::<Numbers>();
+        let second = lazy.get(ctn.resolver()).unwrap();
+        assert!(first.is(&second));
+    }
+
+    #[test]
+    fn subscribe() {
+        struct Counter;
+        impl IShared for Counter {
+            type Pointer = Rc<Observable<RefCell<u32>>>;
+            type Target = u32;
+            type Error = ();
+
+            fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+                Ok(Rc::new(Observable::new(RefCell::new(0))))
+            }
+        }
+
+        let mut ctn = ServiceContainer::new();
+        let counter = ctn.resolver().shared::<Counter>().unwrap();
+        let updates = counter.subscribe();
+
+        counter.access(|n| assert_eq!(*n.assert_healthy(), 0));
+        assert!(updates.try_recv().is_err());
+        counter.access_mut(|n| *n.assert_healthy() += 1);
+        assert!(updates.try_recv().is_ok());
+        assert!(updates.try_recv().is_err());
+    }
 }
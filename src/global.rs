@@ -0,0 +1,195 @@
+//! Shared services that are constructed once for the whole process.
+
+use crate::service_traits::IShared;
+use crate::Resolver;
+use fnv::FnvHashMap;
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+use std::sync::{Mutex, OnceLock};
+
+/// The instance of a global service, which is empty until it's constructed.
+type Slot<P> = OnceLock<P>;
+
+/// The slots of all global services, by the `TypeId` of the service.
+static SLOTS: OnceLock<Mutex<FnvHashMap<TypeId, &'static (dyn Any + Send + Sync)>>> =
+    OnceLock::new();
+
+/// Returns the slot of the global service `S`.
+fn slot<S>() -> &'static Slot<S::Pointer>
+where
+    S: 'static + ?Sized + IShared,
+    S::Pointer: Send + Sync + 'static,
+{
+    let mut slots = SLOTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let slot = *slots.entry(TypeId::of::<S>()).or_insert_with(|| {
+        // The slot is leaked, because it lives as long as the process.
+        let slot: &'static Slot<S::Pointer> = Box::leak(Box::new(OnceLock::new()));
+        slot
+    });
+    slot.downcast_ref()
+        .expect("the slot is stored under the TypeId of its service")
+}
+
+/// Resolves the service `S` once for the whole process, so every container
+/// that resolves a `GlobalOnce<S>` gets the same instance.
+///
+/// The instance is constructed with `S::construct` and the resolver of the
+/// container that resolves it first. If the constructor fails, the error is
+/// returned and the next resolve tries again. Custom constructors of `S`
+/// are not used.
+///
+/// No lock is held while `S::construct` runs, so a global service can
+/// resolve other global services without deadlocking other threads. When
+/// multiple threads resolve the service for the first time at once, each of
+/// them may construct an instance. The first one that is stored wins, and
+/// the others are dropped.
+///
+/// # Global state
+///
+/// This intentionally crosses container boundaries. The instance is never
+/// dropped or torn down, not even when all containers are dropped, and
+/// dependencies of the first container end up in the instance that all
+/// other containers get. Use it only for resources that really are global
+/// to the process, such as a logger.
+///
+/// # Example
+///
+/// ```rust
+/// use rscontainer::{GlobalOnce, IShared, Resolver, ServiceContainer, Shared};
+/// use std::sync::{Arc, Mutex};
+///
+/// struct Logger;
+///
+/// impl IShared for Logger {
+///     type Pointer = Arc<Mutex<Vec<String>>>;
+///     type Target = Vec<String>;
+///     type Error = ();
+///
+///     fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+///         Ok(Arc::new(Mutex::new(Vec::new())))
+///     }
+/// }
+///
+/// let mut first = ServiceContainer::new();
+/// let mut second = ServiceContainer::new();
+/// let a: Shared<GlobalOnce<Logger>> = first.resolver().shared().unwrap();
+/// let b: Shared<GlobalOnce<Logger>> = second.resolver().shared().unwrap();
+/// assert!(a.is(&b));
+/// ```
+pub struct GlobalOnce<S: ?Sized>(PhantomData<S>);
+
+impl<S> IShared for GlobalOnce<S>
+where
+    S: 'static + ?Sized + IShared,
+    S::Pointer: Send + Sync + 'static,
+{
+    type Pointer = S::Pointer;
+    type Target = S::Target;
+    type Error = S::Error;
+
+    fn construct(ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+        let slot = slot::<S>();
+        if let Some(instance) = slot.get() {
+            return Ok(instance.clone());
+        }
+        let instance = S::construct(ctn)?;
+        Ok(slot.get_or_init(|| instance).clone())
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ServiceContainer, Shared};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    static CONSTRUCTED: AtomicU32 = AtomicU32::new(0);
+
+    struct Logger;
+
+    impl IShared for Logger {
+        type Pointer = Arc<Mutex<Vec<String>>>;
+        type Target = Vec<String>;
+        type Error = ();
+
+        fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+            CONSTRUCTED.fetch_add(1, Ordering::SeqCst);
+            Ok(Arc::new(Mutex::new(Vec::new())))
+        }
+    }
+
+    #[test]
+    fn global_once() {
+        let mut first = ServiceContainer::new();
+        let mut second = ServiceContainer::new();
+        let a: Shared<GlobalOnce<Logger>> = first.resolver().shared().unwrap();
+        let b: Shared<GlobalOnce<Logger>> = second.resolver().shared().unwrap();
+        assert!(Shared::ptr_eq(&a, &b));
+        assert_eq!(CONSTRUCTED.load(Ordering::SeqCst), 1);
+
+        drop(first);
+        drop(second);
+        let mut third = ServiceContainer::new();
+        let c: Shared<GlobalOnce<Logger>> = third.resolver().shared().unwrap();
+        assert!(a.is(&c));
+        assert_eq!(CONSTRUCTED.load(Ordering::SeqCst), 1);
+
+        let local: Shared<Logger> = third.resolver().shared().unwrap();
+        assert!(!Arc::ptr_eq(a.inner(), local.inner()));
+    }
+
+    struct Metrics;
+
+    impl IShared for Metrics {
+        type Pointer = Arc<Mutex<Vec<String>>>;
+        type Target = Vec<String>;
+        type Error = ();
+
+        fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+            ctn.shared::<GlobalOnce<Sink>>()?;
+            Ok(Arc::new(Mutex::new(Vec::new())))
+        }
+    }
+
+    struct Sink;
+
+    impl IShared for Sink {
+        type Pointer = Arc<Mutex<Vec<String>>>;
+        type Target = Vec<String>;
+        type Error = ();
+
+        fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+            Ok(Arc::new(Mutex::new(Vec::new())))
+        }
+    }
+
+    #[test]
+    fn global_once_nested() {
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let mut ctn = ServiceContainer::new();
+                    if i % 2 == 0 {
+                        let _: Shared<GlobalOnce<Sink>> = ctn.resolver().shared().unwrap();
+                    }
+                    let metrics: Shared<GlobalOnce<Metrics>> = ctn.resolver().shared().unwrap();
+                    let sink: Shared<GlobalOnce<Sink>> = ctn.resolver().shared().unwrap();
+                    (metrics.into_inner(), sink.into_inner())
+                })
+            })
+            .collect();
+        let instances: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        for (metrics, sink) in &instances[1..] {
+            assert!(Arc::ptr_eq(metrics, &instances[0].0));
+            assert!(Arc::ptr_eq(sink, &instances[0].1));
+        }
+    }
+}
@@ -1,17 +1,62 @@
 //! Internal storage helpers.
 
+use crate::container::{preload, ServiceContainer, ServiceState};
 use crate::pointers::ISharedPointer;
-use crate::service_traits::{IOwned, IShared};
+use crate::service_traits::{Dependency, IDispose, IOwned, IShared};
 use crate::Resolver;
+use fnv::FnvHasher;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem::ManuallyDrop;
 use std::ptr::NonNull;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// The key of a service in the container: the `TypeId` of the service and
+/// the hash of its name, which is zero for unnamed services.
+pub(crate) type ServiceKey = (TypeId, u64);
+
+/// Returns the key of an unnamed service.
+pub(crate) fn service_key<S: ?Sized + 'static>() -> ServiceKey {
+    (TypeId::of::<S>(), 0)
+}
+
+/// Returns the key of a named service.
+pub(crate) fn named_key<S: ?Sized + 'static>(name: &str) -> ServiceKey {
+    let mut hasher = FnvHasher::default();
+    name.hash(&mut hasher);
+    (TypeId::of::<S>(), hasher.finish())
+}
+
+/// The type of a smart pointer. Pointers are compared by `TypeId`, the name
+/// is only used in messages.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PointerType {
+    pub id: TypeId,
+    pub name: &'static str,
+}
+
+impl PointerType {
+    /// Returns the type of the pointer `P`.
+    pub fn of<P: 'static>() -> Self {
+        PointerType {
+            id: TypeId::of::<P>(),
+            name: std::any::type_name::<P>(),
+        }
+    }
+}
 
 /// A raw pointer to a shared instance with drop logic.
 /// This is a type-erased `Rc` or `Arc` that implements `ISharedPointer`.
 #[derive(Debug)]
 pub(crate) struct SharedPtr {
     pub ptr: NonNull<()>,
+    /// The type of the smart pointer, to validate the container.
+    pub pointer: PointerType,
     dtor: unsafe fn(NonNull<()>),
+    clone: unsafe fn(NonNull<()>) -> NonNull<()>,
 }
 
 impl Drop for SharedPtr {
@@ -20,42 +65,259 @@ impl Drop for SharedPtr {
     }
 }
 
+impl Clone for SharedPtr {
+    /// Clones the smart pointer, which increases the reference count.
+    fn clone(&self) -> Self {
+        SharedPtr {
+            ptr: unsafe { (self.clone)(self.ptr) },
+            pointer: self.pointer,
+            dtor: self.dtor,
+            clone: self.clone,
+        }
+    }
+}
+
 impl SharedPtr {
-    pub fn new<P: ISharedPointer>(instance: P) -> Self {
+    pub fn new<P: ISharedPointer + 'static>(instance: P) -> Self {
         SharedPtr {
             ptr: unsafe { instance.into_ptr() },
+            pointer: PointerType::of::<P>(),
             dtor: P::drop_from_ptr,
+            clone: clone_from_ptr::<P>,
         }
     }
+
+    /// Turns the type erased pointer back into the smart pointer, without
+    /// changing the reference count.
+    ///
+    /// # Safety
+    ///
+    /// The pointer should be created from a smart pointer of type `P`.
+    pub unsafe fn into_inner<P: ISharedPointer>(self) -> P {
+        let this = ManuallyDrop::new(self);
+        P::from_ptr(this.ptr)
+    }
+}
+
+/// Clones a type erased smart pointer.
+///
+/// # Safety
+///
+/// `ptr` should be created by the `into_ptr()` method of `P`.
+unsafe fn clone_from_ptr<P: ISharedPointer>(ptr: NonNull<()>) -> NonNull<()> {
+    P::clone_from_ptr(ptr).into_ptr()
 }
 
 /// A custom constructor for a shared instance.
+///
+/// The closure is boxed, so that the `Rc` is a thin pointer that can be
+/// stored in a `SharedPtr`.
 pub(crate) type SharedCtor<S> =
-    fn(Resolver) -> Result<<S as IShared>::Pointer, <S as IShared>::Error>;
+    Rc<Box<dyn Fn(Resolver) -> Result<<S as IShared>::Pointer, <S as IShared>::Error>>>;
+
+/// Constructs a shared instance after its constructor failed, see
+/// `ContainerBuilder::with_shared_fallback`.
+pub(crate) type SharedFallback<S> = Rc<
+    Box<
+        dyn Fn(
+            <S as IShared>::Error,
+            Resolver,
+        ) -> Result<<S as IShared>::Pointer, <S as IShared>::Error>,
+    >,
+>;
 
 /// A custom constructor for an owned instance.
-pub(crate) type OwnedCtor<S> = fn(
-    Resolver,
-    <S as IOwned>::Parameters,
-) -> Result<<S as IOwned>::Instance, <S as IOwned>::Error>;
+pub(crate) type OwnedCtor<S> = Rc<
+    Box<
+        dyn Fn(
+            Resolver,
+            <S as IOwned>::Parameters,
+        ) -> Result<<S as IOwned>::Instance, <S as IOwned>::Error>,
+    >,
+>;
+
+/// A pool of released owned instances, see `ContainerBuilder::with_owned_pool`.
+pub(crate) type OwnedPool<S> = Rc<RefCell<Vec<<S as IOwned>::Instance>>>;
+
+/// Calls `IDispose::dispose` on a type erased shared instance.
+pub(crate) type Disposer = unsafe fn(&SharedPtr) -> Result<(), Box<dyn Any>>;
+
+/// Disposes a shared instance of `S`.
+///
+/// # Safety
+///
+/// `ptr` should be created from `S::Pointer`.
+pub(crate) unsafe fn dispose<S: ?Sized + IDispose>(ptr: &SharedPtr) -> Result<(), Box<dyn Any>>
+where
+    S::Error: 'static,
+{
+    let pointer = S::Pointer::clone_from_ptr(ptr.ptr);
+    S::dispose(&pointer).map_err(|e| Box::new(e) as Box<dyn Any>)
+}
+
+/// Calls `IShared::teardown` on a type erased shared instance.
+pub(crate) type Teardown = unsafe fn(&SharedPtr, Resolver);
+
+/// Tears down a shared instance of `S`.
+///
+/// # Safety
+///
+/// `ptr` should be created from `S::Pointer`.
+pub(crate) unsafe fn teardown<S: ?Sized + IShared>(ptr: &SharedPtr, ctn: Resolver) {
+    let mut pointer = S::Pointer::clone_from_ptr(ptr.ptr);
+    S::teardown(&mut pointer, ctn);
+}
+
+/// Resolves the shared instance of a service with a custom constructor,
+/// see `ServiceContainer::preload`. Returns false if the construction fails.
+pub(crate) type Preloader = fn(&mut ServiceContainer, ServiceKey) -> bool;
 
 /// A service in the container that is type erased.
 #[derive(Default)]
 pub(crate) struct TypeErasedService {
+    /// The type name of the service.
+    pub name: &'static str,
     /// A raw pointer to the shared instance.
     pub shared_ptr: Option<SharedPtr>,
-    /// Custom constructor for a shared instance.
-    pub shared_ctor: Option<SharedCtor<()>>,
-    /// Custom constructor for an owned instance.
-    pub owned_ctor: Option<OwnedCtor<()>>,
+    /// Custom constructor for a shared instance, a type erased `SharedCtor`.
+    pub shared_ctor: Option<SharedPtr>,
+    /// Called when the shared constructor fails, a type erased
+    /// `SharedFallback`.
+    pub shared_fallback: Option<SharedPtr>,
+    /// How many times the shared constructor is tried, and how long to wait
+    /// in between, see `ContainerBuilder::with_shared_retry`.
+    pub shared_retry: Option<(u32, Duration)>,
+    /// Custom constructor for an owned instance, a type erased `OwnedCtor`.
+    pub owned_ctor: Option<SharedPtr>,
+    /// Released owned instances, a type erased `OwnedPool`.
+    pub owned_pool: Option<SharedPtr>,
+    /// The type of the smart pointer of the shared instance, recorded by
+    /// the first registration of an instance or a constructor.
+    pub pointer: Option<PointerType>,
+    /// The type of the smart pointer that the custom shared constructor
+    /// returns.
+    pub ctor_pointer: Option<PointerType>,
+    /// Shuts down the shared instance, see `ContainerBuilder::with_dispose`.
+    pub disposer: Option<Disposer>,
+    /// When the shared instance was stored, relative to the other services.
+    pub stored: u64,
+    /// The dependencies of the shared constructors, see
+    /// `IShared::dependencies`. Recorded by every shared registration.
+    pub dependencies: Option<fn() -> Vec<Dependency>>,
+    /// Tears down the shared instance when the container is dropped.
+    pub teardown: Option<Teardown>,
+    /// Constructs the shared instance with the custom or the default
+    /// constructor.
+    pub preloader: Option<Preloader>,
+}
+
+impl TypeErasedService {
+    /// Creates an empty entry for the service `S`.
+    pub fn of<S: ?Sized>() -> Self {
+        TypeErasedService {
+            name: std::any::type_name::<S>(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the custom constructor for a shared instance.
+    pub fn set_shared_ctor<S: 'static + ?Sized + IShared>(
+        &mut self,
+        ctor: impl Fn(Resolver) -> Result<S::Pointer, S::Error> + 'static,
+    ) {
+        let ctor: SharedCtor<S> = Rc::new(Box::new(ctor));
+        self.shared_ctor = Some(SharedPtr::new(ctor));
+        self.dependencies = Some(S::dependencies);
+        self.teardown = Some(teardown::<S>);
+        self.preloader = Some(preload::<S>);
+        self.pointer.get_or_insert(PointerType::of::<S::Pointer>());
+        self.ctor_pointer = Some(PointerType::of::<S::Pointer>());
+    }
+
+    /// Constructs the shared instance when the container is preloaded, even
+    /// without a custom constructor.
+    pub fn set_eager<S: 'static + ?Sized + IShared>(&mut self) {
+        self.preloader = Some(preload::<S>);
+        self.dependencies = Some(S::dependencies);
+    }
+
+    /// Sets the fallback for when the shared constructor fails.
+    pub fn set_shared_fallback<S: 'static + ?Sized + IShared>(
+        &mut self,
+        fallback: impl Fn(S::Error, Resolver) -> Result<S::Pointer, S::Error> + 'static,
+    ) {
+        let fallback: SharedFallback<S> = Rc::new(Box::new(fallback));
+        self.shared_fallback = Some(SharedPtr::new(fallback));
+        self.dependencies = Some(S::dependencies);
+    }
+
+    /// Retries the shared constructor `attempts` times.
+    pub fn set_shared_retry<S: 'static + ?Sized + IShared>(
+        &mut self,
+        attempts: u32,
+        backoff: Duration,
+    ) {
+        self.shared_retry = Some((attempts, backoff));
+        self.dependencies = Some(S::dependencies);
+    }
+
+    /// Sets the shared instance.
+    pub fn set_shared_ptr<P: ISharedPointer + 'static>(&mut self, instance: P) {
+        self.shared_ptr = Some(SharedPtr::new(instance));
+        self.pointer.get_or_insert(PointerType::of::<P>());
+    }
+
+    /// Sets the shared instance of the service `S`, which is torn down when
+    /// the container is dropped.
+    pub fn set_shared<S: 'static + ?Sized + IShared>(&mut self, instance: S::Pointer) {
+        self.set_shared_ptr(instance);
+        self.teardown = Some(teardown::<S>);
+        self.dependencies = Some(S::dependencies);
+    }
+
+    /// Shuts down the shared instance with `IDispose::dispose`.
+    pub fn set_disposer<S>(&mut self)
+    where
+        S: 'static + ?Sized + IDispose,
+        S::Error: 'static,
+    {
+        self.disposer = Some(dispose::<S>);
+        self.dependencies = Some(S::dependencies);
+    }
+
+    /// Sets the custom constructor for an owned instance.
+    pub fn set_owned_ctor<S: 'static + ?Sized + IOwned>(
+        &mut self,
+        ctor: impl Fn(Resolver, S::Parameters) -> Result<S::Instance, S::Error> + 'static,
+    ) {
+        let ctor: OwnedCtor<S> = Rc::new(Box::new(ctor));
+        self.owned_ctor = Some(SharedPtr::new(ctor));
+    }
+
+    /// Returns the state of the service, or `None` if the entry is empty.
+    ///
+    /// The shared registrations decide the state. Only when there are none,
+    /// the owned constructor is considered.
+    pub fn state(&self) -> Option<ServiceState> {
+        match (self.shared_ptr.is_some(), self.shared_ctor.is_some()) {
+            (true, true) => Some(ServiceState::Constructed),
+            (false, true) => Some(ServiceState::ConstructorOnly),
+            (true, false) => Some(ServiceState::InstanceOnly),
+            (false, false) if self.owned_ctor.is_some() => Some(ServiceState::OwnedOnly),
+            (false, false) => None,
+        }
+    }
 }
 
 impl fmt::Debug for TypeErasedService {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TypeErasedService")
+            .field("name", &self.name)
             .field("shared_ptr", &self.shared_ptr)
             .field("shared_ctor", &self.shared_ctor.is_some())
+            .field("shared_fallback", &self.shared_fallback.is_some())
             .field("owned_ctor", &self.owned_ctor.is_some())
+            .field("owned_pool", &self.owned_pool.is_some())
             .finish()
     }
 }
@@ -89,4 +351,16 @@ mod tests {
         drop(ptr);
         assert_eq!(Rc::strong_count(&thing_clone), 1);
     }
+
+    #[test]
+    fn shared_ptr_clone() {
+        let thing = Rc::new(100);
+        let thing_clone = Rc::clone(&thing);
+        let ptr = SharedPtr::new(thing);
+        let ptr_clone = ptr.clone();
+        assert_eq!(Rc::strong_count(&thing_clone), 3);
+        assert_eq!(ptr.ptr, ptr_clone.ptr);
+        drop(ptr_clone);
+        assert_eq!(Rc::strong_count(&thing_clone), 2);
+    }
 }
@@ -185,23 +185,48 @@
 //! ```
 
 mod access;
+mod async_resolver;
 mod builder;
+#[cfg(feature = "config-file")]
+mod config;
 mod container;
+mod error;
 mod getters;
+mod global;
 mod internal_helpers;
+mod macros;
 mod pointers;
 mod resolver;
+mod scope;
 mod service_traits;
 
-pub use self::access::{Access, Poisoning};
-pub use self::builder::ContainerBuilder;
-pub use self::container::ServiceContainer;
-pub use self::getters::{Instance, Shared};
-pub use self::resolver::Resolver;
-pub use self::service_traits::{IOwned, IShared};
+pub use self::access::{Access, Observable, PoisonOnPanic, Poisoning};
+pub use self::async_resolver::AsyncResolver;
+pub use self::builder::{ContainerBuilder, MergeStrategy, Module};
+#[cfg(feature = "config-file")]
+pub use self::config::{ConfigError, ProviderParams, ProviderRegistry};
+pub use self::container::{Checkpoint, ServiceContainer, ServiceInfo, ServiceState};
+pub use self::error::{
+    BuildError, DisposeError, PreloadError, ResolveError, ValidationError, ValidationIssue,
+};
+pub use self::getters::{Instance, Lazy, Local, Shared, WeakShared};
+pub use self::global::GlobalOnce;
+pub use self::pointers::DefaultShared;
+pub use self::resolver::{ResolveBuilder, Resolver};
+pub use self::scope::{ScopedContainer, ScopedResolver};
+pub use self::service_traits::{
+    ConstructFuture, Dependency, IAsyncOwned, IAsyncShared, IDispose, IOwned, IShared,
+};
+
+#[cfg(feature = "derive")]
+pub use rscontainer_derive::{injectable, IOwned, IShared};
+
+// Lets the derive macros refer to `::rscontainer` inside this crate.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as rscontainer;
 
 /// Types for extending the functionality of rscontainer.
 pub mod internals {
     pub use crate::access::{IAccess, IAccessMut};
-    pub use crate::pointers::ISharedPointer;
+    pub use crate::pointers::{ISharedPointer, IWeakPointer};
 }
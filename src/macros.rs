@@ -0,0 +1,38 @@
+//! Macros for common access patterns.
+
+/// Reads a field of a shared instance, by locking it, cloning the field and
+/// releasing the lock.
+///
+/// `shared_get!(handle.field)` expands to
+/// `handle.access(|s| s.assert_healthy().field.clone())`, so it panics if the
+/// instance is poisoned. Nested fields such as `handle.config.name` work too.
+///
+/// ```rust
+/// # use rscontainer::{IShared, Resolver, ServiceContainer, Shared};
+/// # use std::sync::{Arc, Mutex};
+/// use rscontainer::shared_get;
+///
+/// struct Config {
+///     name: String,
+/// }
+///
+/// impl IShared for Config {
+///     type Pointer = Arc<Mutex<Config>>;
+///     type Target = Config;
+///     type Error = ();
+///
+///     fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+///         Ok(Arc::new(Mutex::new(Config { name: "app".into() })))
+///     }
+/// }
+///
+/// let mut ctn = ServiceContainer::new();
+/// let config: Shared<Config> = ctn.resolver().shared().unwrap();
+/// assert_eq!(shared_get!(config.name), "app");
+/// ```
+#[macro_export]
+macro_rules! shared_get {
+    ($handle:ident $(. $field:tt)+) => {
+        $handle.access(|s| s.assert_healthy() $(. $field)+ .clone())
+    };
+}
@@ -70,8 +70,57 @@ pub unsafe trait ISharedPointer: Sized + Clone {
 
     /// Returns true if `self` points to the same location as `other`.
     fn ptr_eq(&self, other: &Self) -> bool;
+
+    /// Returns the address of the location that `self` points to.
+    ///
+    /// The default implementation takes the address from the raw pointer of
+    /// a clone.
+    fn addr(&self) -> *const () {
+        // SAFETY: the raw pointer is created by `into_ptr()` of this impl
+        // block, and the clone is dropped right after reading the address.
+        unsafe {
+            let ptr = self.clone().into_ptr();
+            Self::drop_from_ptr(ptr);
+            ptr.as_ptr() as *const ()
+        }
+    }
+
+    /// Returns the number of strong pointers to the location that `self`
+    /// points to.
+    fn strong_count(&self) -> usize;
+}
+
+/// A shared pointer that has a weak counterpart, such as `Rc` and `Arc`.
+pub trait IWeakPointer: ISharedPointer {
+    /// The weak pointer, such as `rc::Weak` or `sync::Weak`.
+    type Weak: Clone;
+
+    /// Creates a weak pointer to the same location.
+    fn downgrade(&self) -> Self::Weak;
+
+    /// Creates a shared pointer from a weak pointer, if the instance is
+    /// still alive.
+    fn upgrade(weak: &Self::Weak) -> Option<Self>;
 }
 
+/// A pointer for shared instances that is `Rc<RefCell<T>>` on
+/// single-threaded targets, such as `wasm32` without atomics, and
+/// `Arc<Mutex<T>>` on all other targets.
+///
+/// Use `type Pointer = DefaultShared<Self>` to avoid selecting the pointer
+/// with `cfg` in every service.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+pub type DefaultShared<T> = Rc<std::cell::RefCell<T>>;
+
+/// A pointer for shared instances that is `Rc<RefCell<T>>` on
+/// single-threaded targets, such as `wasm32` without atomics, and
+/// `Arc<Mutex<T>>` on all other targets.
+///
+/// Use `type Pointer = DefaultShared<Self>` to avoid selecting the pointer
+/// with `cfg` in every service.
+#[cfg(not(all(target_arch = "wasm32", not(target_feature = "atomics"))))]
+pub type DefaultShared<T> = Arc<std::sync::Mutex<T>>;
+
 ///////////////////////////////////////////////////////////////////////////////
 // Implementations
 ///////////////////////////////////////////////////////////////////////////////
@@ -89,6 +138,14 @@ unsafe impl<T> ISharedPointer for Rc<T> {
     fn ptr_eq(&self, other: &Self) -> bool {
         Rc::ptr_eq(self, other)
     }
+
+    fn addr(&self) -> *const () {
+        Rc::as_ptr(self) as *const ()
+    }
+
+    fn strong_count(&self) -> usize {
+        Rc::strong_count(self)
+    }
 }
 
 unsafe impl<T> ISharedPointer for Arc<T> {
@@ -104,6 +161,38 @@ unsafe impl<T> ISharedPointer for Arc<T> {
     fn ptr_eq(&self, other: &Self) -> bool {
         Arc::ptr_eq(self, other)
     }
+
+    fn addr(&self) -> *const () {
+        Arc::as_ptr(self) as *const ()
+    }
+
+    fn strong_count(&self) -> usize {
+        Arc::strong_count(self)
+    }
+}
+
+impl<T> IWeakPointer for Rc<T> {
+    type Weak = std::rc::Weak<T>;
+
+    fn downgrade(&self) -> Self::Weak {
+        Rc::downgrade(self)
+    }
+
+    fn upgrade(weak: &Self::Weak) -> Option<Self> {
+        weak.upgrade()
+    }
+}
+
+impl<T> IWeakPointer for Arc<T> {
+    type Weak = std::sync::Weak<T>;
+
+    fn downgrade(&self) -> Self::Weak {
+        Arc::downgrade(self)
+    }
+
+    fn upgrade(weak: &Self::Weak) -> Option<Self> {
+        weak.upgrade()
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -173,6 +262,34 @@ mod tests {
         assert_eq!(Rc::strong_count(&rc_clone), 1);
     }
 
+    #[test]
+    fn default_addr() {
+        #[derive(Clone)]
+        struct Counted(Rc<u32>);
+
+        unsafe impl ISharedPointer for Counted {
+            unsafe fn into_ptr(self) -> NonNull<()> {
+                self.0.into_ptr()
+            }
+
+            unsafe fn from_ptr(ptr: NonNull<()>) -> Self {
+                Counted(Rc::from_ptr(ptr))
+            }
+
+            fn ptr_eq(&self, other: &Self) -> bool {
+                Rc::ptr_eq(&self.0, &other.0)
+            }
+
+            fn strong_count(&self) -> usize {
+                Rc::strong_count(&self.0)
+            }
+        }
+
+        let counted = Counted(Rc::new(100));
+        assert_eq!(counted.addr(), Rc::as_ptr(&counted.0) as *const ());
+        assert_eq!(counted.strong_count(), 1);
+    }
+
     #[test]
     fn arc_from_ptr() {
         let rc = Arc::new(100u32);
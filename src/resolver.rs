@@ -1,6 +1,32 @@
 //! Resolver for the service container.
 
-use crate::{IOwned, IShared, Instance, ServiceContainer, Shared};
+use crate::access::{access_within, IAccessMut, Poisoning};
+use crate::internal_helpers::service_key;
+use crate::pointers::IWeakPointer;
+use crate::{
+    IOwned, IShared, Instance, Lazy, Local, ResolveError, ScopedContainer, ServiceContainer,
+    Shared, WeakShared,
+};
+use std::any::TypeId;
+
+/// Generates the `fanout` methods of the [`Resolver`].
+macro_rules! fanout {
+    ($($name:ident: $first:ident $(, $rest:ident)*;)*) => {$(
+        /// Resolves multiple [`Shared`]s at once and returns them as a tuple.
+        ///
+        /// The services are resolved in order and the first error is
+        /// returned, so all services must have the same error type.
+        pub fn $name<$first $(, $rest)*>(
+            &mut self,
+        ) -> Result<(Shared<$first> $(, Shared<$rest>)*), <$first as IShared>::Error>
+        where
+            $first: ?Sized + IShared + 'static,
+            $($rest: ?Sized + IShared<Error = <$first as IShared>::Error> + 'static,)*
+        {
+            Ok((self.shared::<$first>()? $(, self.shared::<$rest>()?)*))
+        }
+    )*};
+}
 
 /// Used to resolve services from the service container.
 ///
@@ -30,13 +56,218 @@ impl<'ctn> Resolver<'ctn> {
     }
 
     /// Resolves a [`Shared`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the service depends on itself, directly or through other
+    /// services.
     pub fn shared<S: ?Sized + IShared + 'static>(&mut self) -> Result<Shared<S>, S::Error> {
         match self.ctn.resolve_shared::<S>() {
-            Ok(s) => Ok(Shared::new(s)),
+            Ok(s) => Ok(self.ctn.to_shared(s)),
             Err(e) => Err(e),
         }
     }
 
+    /// Resolves a [`Shared`], and reports which service in the chain of
+    /// dependencies failed if the construction fails.
+    ///
+    /// If the service is already being constructed, the circular dependency
+    /// is returned as [`ResolveError::CyclicDependency`] without calling a
+    /// constructor. Constructors that resolve their dependencies with this
+    /// method can pass the error on through their own error type. A cycle
+    /// that closes through [`shared()`] still panics.
+    ///
+    /// [`shared()`]: Resolver::shared
+    pub fn shared_checked<S: ?Sized + IShared + 'static>(
+        &mut self,
+    ) -> Result<Shared<S>, ResolveError<S::Error>> {
+        if let Some(chain) = self.ctn.circular_chain(service_key::<S>()) {
+            return Err(ResolveError::CyclicDependency { chain });
+        }
+        match self.ctn.resolve_shared::<S>() {
+            Ok(s) => Ok(self.ctn.to_shared(s)),
+            Err(error) => Err(ResolveError::Service {
+                error,
+                path: self.ctn.take_failed_path().unwrap_or_default(),
+            }),
+        }
+    }
+
+    fanout! {
+        fanout2: A, B;
+        fanout3: A, B, C;
+        fanout4: A, B, C, D;
+        fanout5: A, B, C, D, E;
+        fanout6: A, B, C, D, E, F;
+        fanout7: A, B, C, D, E, F, G;
+        fanout8: A, B, C, D, E, F, G, H;
+    }
+
+    /// Returns the type names of the services that are being constructed,
+    /// from the outermost to the service whose constructor is running.
+    ///
+    /// Inside a constructor, the second to last name is the service that
+    /// asked for it. The path is empty outside of constructors.
+    pub fn construction_path(&self) -> &[&'static str] {
+        self.ctn.breadcrumbs()
+    }
+
+    /// Resolves a named [`Shared`].
+    ///
+    /// Named instances are stored separately from the unnamed instance and
+    /// from each other. If no instance or constructor is registered under
+    /// the name, the default constructor is used.
+    pub fn named_shared<S: ?Sized + IShared + 'static>(
+        &mut self,
+        name: &str,
+    ) -> Result<Shared<S>, S::Error> {
+        let instance = self.ctn.resolve_named_shared::<S>(name)?;
+        Ok(self.ctn.to_shared(instance))
+    }
+
+    /// Resolves a [`WeakShared`].
+    ///
+    /// The container keeps holding the shared instance, so the weak pointer
+    /// can be upgraded for as long as the container exists.
+    pub fn weak_shared<S: ?Sized + IShared + 'static>(&mut self) -> Result<WeakShared<S>, S::Error>
+    where
+        S::Pointer: IWeakPointer,
+    {
+        self.shared::<S>().map(|shared| WeakShared::new(&shared))
+    }
+
+    /// Returns the shared instance if it is already stored in the container.
+    ///
+    /// Never constructs the instance and leaves the container untouched if
+    /// it is absent. [`IShared::resolved()`] is not called. Use
+    /// [`shared_opt()`](Resolver::shared_opt) to construct a registered
+    /// service that isn't stored yet.
+    pub fn try_shared<S: ?Sized + IShared + 'static>(&mut self) -> Option<Shared<S>> {
+        let instance = self.ctn.get_shared::<S>()?;
+        Some(self.ctn.to_shared(instance))
+    }
+
+    /// Resolves a [`Shared`] only if an instance or a custom constructor of
+    /// the service is registered, for dependencies that may be absent.
+    ///
+    /// Returns `Ok(None)` instead of using the default constructor.
+    pub fn optional_shared<S: ?Sized + IShared + 'static>(
+        &mut self,
+    ) -> Result<Option<Shared<S>>, S::Error> {
+        if !self.ctn.is_registered_shared::<S>() {
+            return Ok(None);
+        }
+        self.shared::<S>().map(Some)
+    }
+
+    /// Resolves a [`Shared`] only if an instance or a custom constructor of
+    /// the service is registered, returns `None` if it isn't or if the
+    /// construction fails.
+    ///
+    /// Use [`optional_shared()`](Resolver::optional_shared) to get the error
+    /// instead.
+    pub fn shared_opt<S: ?Sized + IShared + 'static>(&mut self) -> Option<Shared<S>> {
+        let instance = self.optional_shared::<S>().ok().flatten();
+        if instance.is_none() {
+            // The failure is recovered, so it's not reported as the path of
+            // a later failure.
+            self.ctn.take_failed_path();
+        }
+        instance
+    }
+
+    /// Returns a [`Lazy`] shared instance, which is only resolved when it's
+    /// used for the first time.
+    pub fn lazy_shared<S: ?Sized + IShared + 'static>(&self) -> Lazy<S> {
+        Lazy::new()
+    }
+
+    /// Creates a child container that uses `instance` for the service `S`,
+    /// and resolves all other services through this container.
+    ///
+    /// The override only lives in the child, so this container is not
+    /// changed. Use [`ScopedContainer::resolver()`] to resolve services
+    /// with the override, for example to replace a service with a mock in a
+    /// test.
+    pub fn with_override_shared<S: ?Sized + IShared + 'static>(
+        &self,
+        instance: S::Pointer,
+    ) -> ScopedContainer<'_> {
+        let mut scope = self.ctn.child();
+        scope.insert::<S>(instance);
+        scope
+    }
+
+    /// Resolves a shared instance and gives access to it through a closure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    pub fn with_shared<S, U, F>(&mut self, f: F) -> Result<U, S::Error>
+    where
+        S: ?Sized + IShared + 'static,
+        F: FnOnce(&S::Target) -> U,
+    {
+        let shared = self.shared::<S>()?;
+        Ok(shared.access(|instance| f(instance.assert_healthy())))
+    }
+
+    /// Resolves a shared instance and gives mutable access to it through a
+    /// closure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shared instance is poisoned.
+    pub fn with_shared_mut<S, U, F>(&mut self, f: F) -> Result<U, S::Error>
+    where
+        S: ?Sized + IShared + 'static,
+        S::Pointer: IAccessMut,
+        F: FnOnce(&mut S::Target) -> U,
+    {
+        let shared = self.shared::<S>()?;
+        Ok(shared.access_mut(|instance| f(instance.assert_healthy())))
+    }
+
+    /// Resolves two shared instances and gives access to both through one
+    /// closure.
+    ///
+    /// The instances are always locked in the same order, by `TypeId`, so two
+    /// calls with `A` and `B` swapped can't deadlock each other.
+    ///
+    /// The errors of both services are converted into `E`, so `E` has to
+    /// implement `From` for both. When the services have the same error type,
+    /// that type can be used as `E`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `A` and `B` are the same service, because locking its
+    /// instance twice would deadlock.
+    pub fn access_both<A, B, E, U, F>(&mut self, f: F) -> Result<U, E>
+    where
+        A: ?Sized + IShared + 'static,
+        B: ?Sized + IShared + 'static,
+        E: From<A::Error> + From<B::Error>,
+        F: FnOnce(Poisoning<&A::Target>, Poisoning<&B::Target>) -> U,
+    {
+        assert!(
+            TypeId::of::<A>() != TypeId::of::<B>(),
+            "Cannot access `{}` twice at once",
+            std::any::type_name::<A>()
+        );
+        let a = self.ctn.resolve_shared::<A>()?;
+        let b = self.ctn.resolve_shared::<B>()?;
+        let timeout = self.ctn.access_timeout();
+        if TypeId::of::<A>() < TypeId::of::<B>() {
+            Ok(access_within::<A, _, _, _>(&a, timeout, |a| {
+                access_within::<B, _, _, _>(&b, timeout, |b| f(a, b))
+            }))
+        } else {
+            Ok(access_within::<B, _, _, _>(&b, timeout, |b| {
+                access_within::<A, _, _, _>(&a, timeout, |a| f(a, b))
+            }))
+        }
+    }
+
     /// Resolves an owned instance.
     pub fn owned<S: ?Sized + IOwned + 'static>(
         &mut self,
@@ -45,6 +276,93 @@ impl<'ctn> Resolver<'ctn> {
         self.ctn.resolve_owned::<S>(params)
     }
 
+    /// Resolves an owned instance wrapped in a [`Local`], the counterpart of
+    /// [`Shared`] for owned instances.
+    pub fn local<S: ?Sized + IOwned + 'static>(
+        &mut self,
+        params: S::Parameters,
+    ) -> Result<Local<S>, S::Error> {
+        self.owned::<S>(params).map(Local::new)
+    }
+
+    /// Resolves an owned instance from borrowed parameters, see
+    /// [`IOwned::construct_ref()`].
+    ///
+    /// A custom constructor takes the parameters by value, so it gets a
+    /// clone.
+    pub fn owned_ref<S: ?Sized + IOwned + 'static>(
+        &mut self,
+        params: &S::Parameters,
+    ) -> Result<S::Instance, S::Error>
+    where
+        S::Parameters: Clone,
+    {
+        self.ctn.resolve_owned_ref::<S>(params)
+    }
+
+    /// Resolves an owned instance with the default parameters.
+    pub fn owned_default<S: ?Sized + IOwned + 'static>(&mut self) -> Result<S::Instance, S::Error>
+    where
+        S::Parameters: Default,
+    {
+        self.ctn.resolve_owned::<S>(Default::default())
+    }
+
+    /// Resolves an owned instance, returns `None` if the construction fails.
+    ///
+    /// Use [`owned()`](Resolver::owned) to get the error instead.
+    pub fn try_owned<S: ?Sized + IOwned + 'static>(
+        &mut self,
+        params: S::Parameters,
+    ) -> Option<S::Instance> {
+        let instance = self.ctn.resolve_owned::<S>(params).ok();
+        if instance.is_none() {
+            // The failure is recovered, so it's not reported as the path of
+            // a later failure.
+            self.ctn.take_failed_path();
+        }
+        instance
+    }
+
+    /// Resolves an owned instance only if a custom constructor of the service
+    /// is registered, for dependencies that may be absent.
+    ///
+    /// Returns `Ok(None)` instead of using the default constructor.
+    pub fn optional_owned<S: ?Sized + IOwned + 'static>(
+        &mut self,
+        params: S::Parameters,
+    ) -> Result<Option<S::Instance>, S::Error> {
+        if !self.ctn.is_registered_owned::<S>() {
+            return Ok(None);
+        }
+        self.owned::<S>(params).map(Some)
+    }
+
+    /// Resolves an owned instance from the pool of released instances.
+    ///
+    /// Constructs a new instance with `params` if the pool is empty or if
+    /// pooling is not enabled. A reused instance ignores `params` and
+    /// [`IOwned::resolved()`] is not called again. See
+    /// [`ContainerBuilder::with_owned_pool()`].
+    ///
+    /// [`ContainerBuilder::with_owned_pool()`]: crate::ContainerBuilder::with_owned_pool
+    pub fn owned_pooled<S: ?Sized + IOwned + 'static>(
+        &mut self,
+        params: S::Parameters,
+    ) -> Result<S::Instance, S::Error> {
+        self.ctn.resolve_owned_pooled::<S>(params)
+    }
+
+    /// Resolves an owned instance, with parameters that are only computed
+    /// when the instance is actually constructed.
+    pub fn owned_with<S, F>(&mut self, make_params: F) -> Result<S::Instance, S::Error>
+    where
+        S: ?Sized + IOwned + 'static,
+        F: FnOnce() -> S::Parameters,
+    {
+        self.ctn.resolve_owned::<S>(make_params())
+    }
+
     /// Resolves an [`Instance::Shared`].
     pub fn shared_instance<S: ?Sized + IShared + IOwned + 'static>(
         &mut self,
@@ -65,4 +383,77 @@ impl<'ctn> Resolver<'ctn> {
             Err(e) => Err(e)
         }
     }
+
+    /// Starts filling in the dependencies of `value` one by one, see
+    /// [`ResolveBuilder`].
+    pub fn resolve_builder<T, E>(&mut self, value: T) -> ResolveBuilder<'_, T, E> {
+        ResolveBuilder {
+            resolver: Resolver::new(self.ctn),
+            value: Ok(value),
+        }
+    }
+}
+
+/// Fills in the dependencies of a value one by one, and stops at the first
+/// dependency that fails.
+///
+/// The errors of the dependencies are converted into `E` with `From`.
+///
+/// ```rust
+/// # use rscontainer::{Resolver, Shared};
+/// struct Server {
+///     config: Option<Shared<()>>,
+///     logger: Option<()>,
+/// }
+///
+/// fn construct(mut ctn: Resolver) -> Result<Server, ()> {
+///     ctn.resolve_builder(Server { config: None, logger: None })
+///         .shared_field(|server, config| server.config = Some(config))
+///         .owned_field::<(), _>((), |server, logger| server.logger = Some(logger))
+///         .finish()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ResolveBuilder<'r, T, E> {
+    resolver: Resolver<'r>,
+    value: Result<T, E>,
+}
+
+impl<'r, T, E> ResolveBuilder<'r, T, E> {
+    /// Resolves a [`Shared`] and passes it to `set`.
+    pub fn shared_field<S, F>(mut self, set: F) -> Self
+    where
+        S: ?Sized + IShared + 'static,
+        E: From<S::Error>,
+        F: FnOnce(&mut T, Shared<S>),
+    {
+        if let Ok(value) = &mut self.value {
+            match self.resolver.shared::<S>() {
+                Ok(shared) => set(value, shared),
+                Err(e) => self.value = Err(e.into()),
+            }
+        }
+        self
+    }
+
+    /// Resolves an owned instance and passes it to `set`.
+    pub fn owned_field<S, F>(mut self, params: S::Parameters, set: F) -> Self
+    where
+        S: ?Sized + IOwned + 'static,
+        E: From<S::Error>,
+        F: FnOnce(&mut T, S::Instance),
+    {
+        if let Ok(value) = &mut self.value {
+            match self.resolver.owned::<S>(params) {
+                Ok(owned) => set(value, owned),
+                Err(e) => self.value = Err(e.into()),
+            }
+        }
+        self
+    }
+
+    /// Returns the value, or the error of the first dependency that failed.
+    pub fn finish(self) -> Result<T, E> {
+        self.value
+    }
 }
@@ -0,0 +1,209 @@
+//! Child containers that inherit the services of a parent container.
+
+use crate::internal_helpers::{ServiceKey, TypeErasedService};
+use crate::service_traits::IShared;
+use crate::{Resolver, ServiceContainer};
+use fnv::FnvHashMap;
+use std::marker::PhantomData;
+
+/// The resolver of a [`ScopedContainer`].
+///
+/// This is the same type as [`Resolver`], so constructors resolve their
+/// dependencies through the scope without any changes.
+pub type ScopedResolver<'ctn> = Resolver<'ctn>;
+
+/// A child container, for example for a single request or session.
+///
+/// Created with [`ServiceContainer::child()`] or
+/// [`ContainerBuilder::build_child()`](crate::ContainerBuilder::build_child).
+///
+/// Services that are inserted or constructed in the scope are stored in the
+/// scope and dropped together with it. A shared service that the scope
+/// can't find locally is looked up in the parent: an existing instance of
+/// the parent is used directly, a custom constructor of the parent is used
+/// to construct a new instance in the scope. The parent is borrowed
+/// immutably and is never changed by the scope.
+///
+/// Constructors that run in the scope resolve their dependencies through the
+/// scope as well, so a scoped service that depends on another scoped service
+/// gets the instance of the scope.
+#[derive(Debug)]
+pub struct ScopedContainer<'p> {
+    /// The services of the scope, with a pointer to the parent.
+    ctn: ServiceContainer,
+    /// Keeps the parent borrowed for as long as the scope exists.
+    parent: PhantomData<&'p ServiceContainer>,
+}
+
+impl<'p> ScopedContainer<'p> {
+    /// Creates a new scope with the specified services.
+    pub(crate) fn new(
+        parent: &'p ServiceContainer,
+        services: FnvHashMap<ServiceKey, TypeErasedService>,
+    ) -> Self {
+        Self {
+            // SAFETY: the parent is borrowed for 'p, so it outlives the
+            // child and can't be mutated.
+            ctn: unsafe { ServiceContainer::new_child(parent, services) },
+            parent: PhantomData,
+        }
+    }
+
+    /// Inserts a shared instance into the scope, overriding the instance of
+    /// the parent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scope already contains an instance of the service.
+    pub fn insert<S: 'static + ?Sized + IShared>(&mut self, instance: S::Pointer) {
+        self.ctn.insert::<S>(instance);
+    }
+
+    /// Creates a nested scope that inherits the services of this scope.
+    pub fn child(&self) -> ScopedContainer<'_> {
+        self.ctn.child()
+    }
+
+    /// Creates a resolver that can be used to resolve services.
+    pub fn resolver(&mut self) -> ScopedResolver<'_> {
+        self.ctn.resolver()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Access, IOwned, Shared};
+    use std::rc::Rc;
+
+    struct Session;
+
+    impl IShared for Session {
+        type Pointer = Rc<Access<u32>>;
+        type Target = u32;
+        type Error = ();
+
+        fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+            Ok(Rc::new(Access::new(1)))
+        }
+    }
+
+    struct Greeting;
+
+    impl IShared for Greeting {
+        type Pointer = Rc<Access<String>>;
+        type Target = String;
+        type Error = ();
+
+        fn construct(mut ctn: Resolver) -> Result<Self::Pointer, Self::Error> {
+            let session = ctn.shared::<Session>()?;
+            let id = session.access(|id| *id.assert_healthy());
+            Ok(Rc::new(Access::new(format!("session {}", id))))
+        }
+    }
+
+    impl IOwned for Greeting {
+        type Instance = String;
+        type Parameters = ();
+        type Error = ();
+
+        fn construct(_: Resolver, _: ()) -> Result<Self::Instance, Self::Error> {
+            Ok(String::from("hello"))
+        }
+    }
+
+    #[test]
+    fn inherits_parent_instance() {
+        let mut parent = ServiceContainer::new();
+        let session: Shared<Session> = parent.resolver().shared().unwrap();
+
+        let mut scope = parent.child();
+        let scoped: Shared<Session> = scope.resolver().shared().unwrap();
+        assert!(session.is(&scoped));
+    }
+
+    #[test]
+    fn override_does_not_affect_parent() {
+        let mut parent = ServiceContainer::new();
+        let mut scope = parent.child();
+        scope.insert::<Session>(Rc::new(Access::new(2)));
+        let scoped: Shared<Session> = scope.resolver().shared().unwrap();
+        assert_eq!(***scoped.inner(), 2);
+        drop(scope);
+
+        let session: Shared<Session> = parent.resolver().shared().unwrap();
+        assert_eq!(***session.inner(), 1);
+    }
+
+    #[test]
+    fn with_override_shared() {
+        let mut parent = ServiceContainer::new();
+        let resolver = parent.resolver();
+        let mut scope = resolver.with_override_shared::<Session>(Rc::new(Access::new(5)));
+        let greeting: Shared<Greeting> = scope.resolver().shared().unwrap();
+        assert_eq!(greeting.access(|g| g.assert_healthy().clone()), "session 5");
+        assert_eq!(scope.resolver().owned::<Greeting>(()).unwrap(), "hello");
+        drop(scope);
+
+        assert!(parent.resolver().try_shared::<Session>().is_none());
+        assert!(parent.resolver().try_shared::<Greeting>().is_none());
+    }
+
+    #[test]
+    fn scoped_instances_are_dropped_with_scope() {
+        let mut parent = ServiceContainer::new();
+        let mut scope = parent.child();
+        let scoped: Shared<Session> = scope.resolver().shared().unwrap();
+        assert_eq!(Rc::strong_count(scoped.inner()), 2);
+
+        drop(scope);
+        assert_eq!(Rc::strong_count(scoped.inner()), 1);
+        assert!(parent.resolver().try_shared::<Session>().is_none());
+    }
+
+    #[test]
+    fn dependencies_resolve_in_scope() {
+        let parent = ServiceContainer::new();
+        let mut scope = parent.child();
+        scope.insert::<Session>(Rc::new(Access::new(7)));
+        let greeting: Shared<Greeting> = scope.resolver().shared().unwrap();
+        assert_eq!(greeting.access(|g| g.assert_healthy().clone()), "session 7");
+    }
+
+    #[test]
+    fn inherits_parent_constructors() {
+        let parent = ServiceContainer::builder()
+            .with_shared_constructor::<Session>(|_| Ok(Rc::new(Access::new(3))))
+            .with_owned_constructor::<Greeting>(|_, _| Ok(String::from("hi")))
+            .build();
+
+        let mut scope = parent.child();
+        let session: Shared<Session> = scope.resolver().shared().unwrap();
+        assert_eq!(***session.inner(), 3);
+        assert_eq!(scope.resolver().owned::<Greeting>(()).unwrap(), "hi");
+    }
+
+    #[test]
+    fn build_child_overrides_constructor() {
+        let parent = ServiceContainer::new();
+        let mut scope = ServiceContainer::builder()
+            .with_shared_constructor::<Session>(|_| Ok(Rc::new(Access::new(4))))
+            .build_child(&parent);
+        let greeting: Shared<Greeting> = scope.resolver().shared().unwrap();
+        assert_eq!(greeting.access(|g| g.assert_healthy().clone()), "session 4");
+    }
+
+    #[test]
+    fn nested_scopes() {
+        let mut parent = ServiceContainer::new();
+        let session: Shared<Session> = parent.resolver().shared().unwrap();
+        let scope = parent.child();
+        let mut nested = scope.child();
+        let nested_session: Shared<Session> = nested.resolver().shared().unwrap();
+        assert!(session.is(&nested_session));
+    }
+}
@@ -2,7 +2,11 @@
 
 use super::access::{Access, IAccess};
 use super::pointers::ISharedPointer;
-use crate::Resolver;
+use crate::{AsyncResolver, Resolver};
+use std::any::TypeId;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
 use std::rc::Rc;
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -16,11 +20,17 @@ pub trait IShared {
     /// * `Rc<Access<T>>`
     /// * `Rc<Cell<T>>`
     /// * `Rc<RefCell<T>>`
+    /// * `Rc<OnceCell<T>>`
+    /// * `Rc<LazyCell<T>>`
     /// * `Arc<Access<T>>`
     /// * `Arc<Mutex<T>>`
     /// * `Arc<RwLock<T>>`
+    /// * `Arc<OnceLock<T>>`
+    /// * `Arc<LazyLock<T>>`
     ///
-    /// Where `T` is equal to `Self::Target`.
+    /// Where `T` is equal to `Self::Target`. `OnceCell` and `OnceLock` are
+    /// initialized with `T::default()` on first access, unless the service
+    /// set a value before.
     ///
     /// Use the [`Access`] wrapper if the type is read-only or already
     /// implements interior mutability.
@@ -41,7 +51,73 @@ pub trait IShared {
     fn construct(ctn: Resolver) -> Result<Self::Pointer, Self::Error>;
 
     /// Called each time after the service is resolved from the container.
+    ///
+    /// `this` is the smart pointer, not the target. To modify the instance
+    /// itself, for example to wire up a back-reference, use the access
+    /// methods of the pointer, such as [`IAccessMut::access_mut()`].
+    ///
+    /// ```rust
+    /// # use rscontainer::{IShared, Resolver};
+    /// use rscontainer::internals::IAccessMut;
+    /// use std::sync::{Arc, Mutex, Weak};
+    ///
+    /// struct Node {
+    ///     this: Weak<Mutex<Node>>,
+    /// }
+    ///
+    /// impl IShared for Node {
+    ///     type Pointer = Arc<Mutex<Node>>;
+    ///     type Target = Node;
+    ///     type Error = ();
+    ///
+    ///     fn construct(_: Resolver) -> Result<Self::Pointer, Self::Error> {
+    ///         Ok(Arc::new(Mutex::new(Node { this: Weak::new() })))
+    ///     }
+    ///
+    ///     fn resolved(this: &mut Self::Pointer, _: Resolver) {
+    ///         let weak = Arc::downgrade(this);
+    ///         this.access_mut(|node| node.assert_healthy().this = weak);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Exclusive borrows
+    ///
+    /// The service can be resolved while the caller is accessing it, for
+    /// example when a dependency is resolved inside `access_mut()`. Cloning
+    /// the pointer is fine, but accessing the instance in this hook would
+    /// then panic for a `RefCell`, or deadlock for a lock. Use
+    /// [`IAccessMut::try_access_mut()`] instead when that can happen, and
+    /// skip the work if the instance is unavailable.
+    ///
+    /// [`IAccessMut::access_mut()`]: crate::internals::IAccessMut::access_mut
+    /// [`IAccessMut::try_access_mut()`]: crate::internals::IAccessMut::try_access_mut
     fn resolved(_this: &mut Self::Pointer, _ctn: Resolver) {}
+
+    /// Called once when the container releases its pointer to the shared
+    /// instance: when the container is dropped, or when the instance is
+    /// reset, replaced, disposed, restored away or overwritten by a merge.
+    ///
+    /// Use this to close files, connections or thread pools
+    /// deterministically. Clones of the pointer outside the container don't
+    /// keep the hook from running, and they don't run it again. Instances
+    /// that are taken out with [`ServiceContainer::remove_shared()`] are not
+    /// torn down.
+    ///
+    /// A hook that panics while the container is dropped during another
+    /// panic aborts the process, like any destructor that panics while
+    /// unwinding.
+    ///
+    /// [`ServiceContainer::remove_shared()`]: crate::ServiceContainer::remove_shared
+    fn teardown(_this: &mut Self::Pointer, _ctn: Resolver) {}
+
+    /// The services that the constructor resolves, used by
+    /// [`ContainerBuilder::try_build()`] to check that they can be resolved.
+    ///
+    /// [`ContainerBuilder::try_build()`]: crate::ContainerBuilder::try_build
+    fn dependencies() -> Vec<Dependency> {
+        Vec::new()
+    }
 }
 
 /// A type that can be used as an owned service.
@@ -59,10 +135,141 @@ pub trait IOwned {
     /// Constructs an instance of the shared service.
     fn construct(ctn: Resolver, params: Self::Parameters) -> Result<Self::Instance, Self::Error>;
 
+    /// Constructs an instance from borrowed parameters, see
+    /// [`Resolver::owned_ref()`].
+    ///
+    /// Clones the parameters and calls `construct` by default. Override it
+    /// to avoid the clone, for example for a large configuration.
+    fn construct_ref(
+        ctn: Resolver,
+        params: &Self::Parameters,
+    ) -> Result<Self::Instance, Self::Error>
+    where
+        Self::Parameters: Clone,
+    {
+        Self::construct(ctn, params.clone())
+    }
+
     /// Called each time after the service is resolved from the container.
     fn resolved(_this: &mut Self::Instance, _ctn: Resolver) {}
 }
 
+/// A shared service that has to be shut down gracefully, for example to
+/// flush buffers or close connections.
+///
+/// Register it with [`ContainerBuilder::with_dispose()`] and call
+/// [`ServiceContainer::dispose_all()`] before the container is dropped.
+///
+/// [`ContainerBuilder::with_dispose()`]: crate::ContainerBuilder::with_dispose
+/// [`ServiceContainer::dispose_all()`]: crate::ServiceContainer::dispose_all
+pub trait IDispose: IShared {
+    /// Shuts down the shared instance. Called before
+    /// [`IShared::teardown()`].
+    fn dispose(pointer: &Self::Pointer) -> Result<(), Self::Error>;
+}
+
+/// The future that is returned by the constructors of asynchronous services.
+pub type ConstructFuture<'ctn, T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + 'ctn>>;
+
+/// A type that can be used as a shared service that is constructed
+/// asynchronously.
+///
+/// Resolved with [`AsyncResolver::shared()`], which returns the smart
+/// pointer to the instance. The normal [`Resolver`] can't resolve
+/// asynchronous services, because that would block the current thread.
+pub trait IAsyncShared {
+    /// The type of the smart pointer to the service. See
+    /// [`IShared::Pointer`].
+    type Pointer: ISharedPointer + IAccess<Target = Self::Target>;
+
+    /// The type that is used to access the shared instance.
+    type Target;
+
+    /// The type of the error that can occur when constructing or resolving
+    /// this service.
+    type Error;
+
+    /// Constructs an instance of the shared service.
+    fn construct(ctn: AsyncResolver<'_>) -> ConstructFuture<'_, Self::Pointer, Self::Error>;
+}
+
+/// A type that can be used as an owned service that is constructed
+/// asynchronously.
+///
+/// Resolved with [`AsyncResolver::owned()`].
+pub trait IAsyncOwned {
+    /// The type of the owned service.
+    type Instance;
+
+    /// Optional parameters for the `construct` method.
+    type Parameters;
+
+    /// The type of the error that can occur when constructing or resolving
+    /// this service.
+    type Error;
+
+    /// Constructs an instance of the owned service.
+    fn construct(
+        ctn: AsyncResolver<'_>,
+        params: Self::Parameters,
+    ) -> ConstructFuture<'_, Self::Instance, Self::Error>;
+}
+
+///////////////////////////////////////////////////////////////////////////////
+// Dependencies
+///////////////////////////////////////////////////////////////////////////////
+
+/// A service that a constructor resolves, see [`IShared::dependencies()`].
+#[derive(Clone, Copy)]
+pub struct Dependency {
+    pub(crate) type_id: TypeId,
+    pub(crate) name: &'static str,
+    /// The dependencies of the default constructor, or `None` if the
+    /// service must be registered.
+    pub(crate) default: Option<fn() -> Vec<Dependency>>,
+}
+
+impl Dependency {
+    /// A shared service, which is constructed with [`IShared::construct()`]
+    /// when it's not registered.
+    pub fn shared<S: 'static + ?Sized + IShared>() -> Self {
+        Dependency {
+            type_id: TypeId::of::<S>(),
+            name: std::any::type_name::<S>(),
+            default: Some(S::dependencies),
+        }
+    }
+
+    /// A service that must be registered, for example one whose default
+    /// constructor always fails.
+    pub fn registered<S: 'static + ?Sized>() -> Self {
+        Dependency {
+            type_id: TypeId::of::<S>(),
+            name: std::any::type_name::<S>(),
+            default: None,
+        }
+    }
+
+    /// Returns the `TypeId` of the service.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Returns the type name of the service.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl fmt::Debug for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dependency")
+            .field("name", &self.name)
+            .field("registered", &self.default.is_none())
+            .finish()
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // Implementations
 ///////////////////////////////////////////////////////////////////////////////
@@ -86,3 +293,188 @@ impl IOwned for () {
         Ok(())
     }
 }
+
+///////////////////////////////////////////////////////////////////////////////
+// Tests
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::{injectable, IOwned, IShared, ServiceContainer, Shared};
+    use std::cell::RefCell;
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Port(u16);
+
+    impl IOwned for Port {
+        type Instance = Port;
+        type Parameters = u16;
+        type Error = Infallible;
+
+        fn construct(_: Resolver, port: u16) -> Result<Self::Instance, Self::Error> {
+            Ok(Port(port))
+        }
+    }
+
+    #[derive(Default, IShared)]
+    struct Config {
+        name: String,
+    }
+
+    #[derive(IShared)]
+    struct Server {
+        #[shared]
+        config: Shared<Config>,
+        #[owned(8080)]
+        port: Port,
+        #[owned]
+        fallback: Port,
+        requests: u32,
+    }
+
+    #[derive(IShared)]
+    #[shared(error = "String")]
+    struct Unit;
+
+    #[derive(IShared, IOwned)]
+    struct Limits {
+        max: u32,
+    }
+
+    impl Default for Limits {
+        fn default() -> Self {
+            Limits { max: 100 }
+        }
+    }
+
+    #[derive(Default, IShared)]
+    #[shared(pointer = "Rc<RefCell<Self>>")]
+    struct Counter {
+        count: u32,
+    }
+
+    #[derive(IShared)]
+    #[shared(
+        pointer = "Rc<Access<Self>>",
+        error = "String",
+        constructor = "connect"
+    )]
+    struct Connection {
+        url: String,
+    }
+
+    fn connect(_: Resolver) -> Result<Rc<Access<Connection>>, String> {
+        Ok(Rc::new(Access::new(Connection {
+            url: "postgres://localhost".into(),
+        })))
+    }
+
+    #[derive(IShared, IOwned)]
+    struct Cache {
+        #[owned(80)]
+        port: Port,
+        entries: Vec<u32>,
+    }
+
+    #[derive(IOwned)]
+    #[owned(params = "u16", error = "String", constructor = "listen")]
+    struct Listener {
+        port: u16,
+    }
+
+    fn listen(_: Resolver, port: u16) -> Result<Listener, String> {
+        match port {
+            0 => Err("invalid port".into()),
+            port => Ok(Listener { port }),
+        }
+    }
+
+    #[derive(Debug)]
+    struct AppError;
+
+    impl From<Infallible> for AppError {
+        fn from(error: Infallible) -> Self {
+            match error {}
+        }
+    }
+
+    #[injectable(pointer = "Rc<RefCell<Self>>", error = "AppError")]
+    struct App {
+        #[inject]
+        config: Shared<Config>,
+        #[inject]
+        counter: Shared<Counter>,
+        started: bool,
+    }
+
+    #[test]
+    fn derive_shared() {
+        let mut ctn = ServiceContainer::new();
+        let server: Shared<Server> = ctn.resolver().shared().unwrap();
+        let config: Shared<Config> = ctn.resolver().shared().unwrap();
+
+        let server: Arc<Mutex<Server>> = server.into_inner();
+        let server = server.lock().unwrap();
+        assert!(server.config.is(&config));
+        assert_eq!(server.port.0, 8080);
+        assert_eq!(server.fallback.0, 0);
+        assert_eq!(server.requests, 0);
+        assert_eq!(config.inner().lock().unwrap().name, "");
+
+        let unit: Result<Shared<Unit>, String> = ctn.resolver().shared();
+        assert!(unit.is_ok());
+    }
+
+    #[test]
+    fn derive_shared_uses_struct_default() {
+        let mut ctn = ServiceContainer::new();
+        let limits: Shared<Limits> = ctn.resolver().shared().unwrap();
+        assert_eq!(limits.inner().lock().unwrap().max, 100);
+    }
+
+    #[test]
+    fn derive_shared_attributes() {
+        let mut ctn = ServiceContainer::new();
+
+        let counter: Shared<Counter> = ctn.resolver().shared().unwrap();
+        counter.inner().borrow_mut().count += 1;
+        assert_eq!(counter.inner().borrow().count, 1);
+
+        let connection: Shared<Connection> = ctn.resolver().shared().unwrap();
+        assert_eq!(connection.as_ref().url, "postgres://localhost");
+    }
+
+    #[test]
+    fn injectable() {
+        let mut ctn = ServiceContainer::new();
+        let app: Shared<App> = ctn.resolver().shared().unwrap();
+        let counter: Shared<Counter> = ctn.resolver().shared().unwrap();
+
+        let app = app.inner().borrow();
+        assert!(app.counter.is(&counter));
+        assert_eq!(app.config.inner().lock().unwrap().name, "");
+        assert!(!app.started);
+    }
+
+    #[test]
+    fn derive_owned() {
+        let mut ctn = ServiceContainer::new();
+
+        let cache = ctn.resolver().owned::<Cache>(()).unwrap();
+        assert_eq!(cache.port.0, 80);
+        assert!(cache.entries.is_empty());
+        let shared: Shared<Cache> = ctn.resolver().shared().unwrap();
+        assert_eq!(shared.inner().lock().unwrap().port.0, 80);
+
+        let limits = ctn.resolver().owned::<Limits>(()).unwrap();
+        assert_eq!(limits.max, 100);
+
+        let listener = ctn.resolver().owned::<Listener>(8080).unwrap();
+        assert_eq!(listener.port, 8080);
+        let error = ctn.resolver().owned::<Listener>(0).err();
+        assert_eq!(error.as_deref(), Some("invalid port"));
+    }
+}